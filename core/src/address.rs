@@ -1,7 +1,8 @@
 use nom::{
   bytes::complete::{tag_no_case, take_while_m_n},
-  combinator::map,
-  sequence::tuple,
+  character::complete::satisfy,
+  combinator::{map, not},
+  sequence::{terminated, tuple},
 };
 
 use nom_tracable::tracable_parser;
@@ -14,10 +15,15 @@ fn valid_address_char(c: char) -> bool {
   c.is_ascii_hexdigit()
 }
 
+/// Addresses are exactly 40 hex digits without `_` separators; any other `0x`
+/// literal, e.g. `0xff` or a 64 digit hash, is a number
 #[tracable_parser]
 pub fn address(i: Span) -> Result {
   map(
-    tuple((tag_no_case("0x"), take_while_m_n(40, 40, valid_address_char))), // Ethereum EOA: ^(0x)?[0-9a-fA-F]{40}$
+    terminated(
+      tuple((tag_no_case("0x"), take_while_m_n(40, 40, valid_address_char))), // Ethereum EOA: ^(0x)?[0-9a-fA-F]{40}$
+      not(satisfy(|c| valid_address_char(c) || c == '_')),
+    ),
     |(first, rest): (Span, Span)| {
       let mut a = String::from(*first.fragment());
       a.push_str(rest.fragment());
@@ -52,7 +58,16 @@ mod test {
     Ok(())
   }
 
-  #[rstest(input, case(""), case("0x"), case("0X"), case("0xcac725bef4f114f7a463c3fc"))]
+  #[rstest(
+    input,
+    case(""),
+    case("0x"),
+    case("0X"),
+    case("0xcac725bef4f114f7a463c3fc"),
+    case("0xcac725bef4f114f728cbcfd744a731c2a463c3fc0"),
+    case("0xcac725bef4f114f728cbcfd744a731c2a463c3fc_"),
+    case("0xcac725bef4f114f728cbcfd744a731c2a463c3fcac725bef4f114f728cbcfd744a731c2")
+  )]
   fn test_address_invalid(input: &'static str, info: TracableInfo) -> Result {
     assert!(address(Span::new_extra(input, info)).is_err());
     Ok(())
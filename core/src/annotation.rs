@@ -0,0 +1,117 @@
+use std::rc::Rc;
+
+use nom::{
+  character::complete::{char, space0},
+  combinator::map,
+  sequence::{pair, preceded},
+};
+use nom_tracable::tracable_parser;
+
+use crate::{function_args, identifier, Annotation, Node, Result, Span, Token};
+
+/// `@retry(3)` or `@label("rebalance-1")`. Unlike handles such as `@treasury`,
+/// annotations always take arguments, which like a function's may follow a space.
+#[tracable_parser]
+pub fn annotation(i: Span) -> Result {
+  map(
+    preceded(char('@'), pair(identifier, preceded(space0, function_args))),
+    |(name, args)| {
+      let annotation = Annotation {
+        name: Rc::new(name),
+        args,
+      };
+      Node::new(Token::Annotation(annotation), &i)
+    },
+  )(i)
+}
+
+#[cfg(test)]
+mod test {
+  use crate::test::{info, Result};
+  use crate::*;
+  use nom::combinator::all_consuming;
+  use nom_tracable::TracableInfo;
+  use rstest::rstest;
+
+  #[rstest(input, expected,
+      case("@retry(3)", annotation!("retry", number!(3))),
+      case("@Timeout(30s)", annotation!("timeout", duration!(30_000))),
+      case(r#"@label("rebalance-1")"#, annotation!("label", string!("rebalance-1"))),
+      case("@idempotent()", annotation!("idempotent")),
+      case("@retry (3)", annotation!("retry", number!(3))),
+      case(
+        "@retry(3, backoff=5s)",
+        annotation!("retry", number!(3), opt!("backoff", duration!(5_000)))
+      ),
+    )]
+  fn test_annotation(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let (span, node) = annotation(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    node.assert_same_token(&node!(expected));
+    Ok(())
+  }
+
+  #[rstest(input, case("@treasury"), case("@alice.hot"), case("@retry\n(3)"), case("retry(3)"))]
+  fn test_annotation_invalid(input: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(all_consuming(annotation)(span).is_err());
+  }
+
+  #[rstest(input, expected, annotations,
+      case(
+        "@retry(3) @timeout(30s) swap(usdc, eth)",
+        function!("swap", none, ident!("usdc"), ident!("eth")),
+        vec![annotation!("retry", number!(3)), annotation!("timeout", duration!(30_000))]
+      ),
+      case(
+        "@label(\"rebalance-1\")\nevery 1h {\n  @retry(2)\n  rebalance()\n}",
+        every!(
+          duration!(3_600_000),
+          body!(function!("rebalance"))
+        ),
+        vec![annotation!("label", string!("rebalance-1"))]
+      ),
+      case(
+        "@retry(3)\n# retried, as the pool is flaky\n\n@timeout(30s) # at most\nswap()",
+        function!("swap"),
+        vec![annotation!("retry", number!(3)), annotation!("timeout", duration!(30_000))]
+      ),
+      case(
+        "@retry(3)\n## Swaps\n# vxl-ignore-next-line\nswap()",
+        function!("swap"),
+        vec![annotation!("retry", number!(3))]
+      ),
+      case("@retry (3) swap()", function!("swap"), vec![annotation!("retry", number!(3))]),
+      case("@treasury.hot", handle!("treasury", "hot"), vec![]),
+      case("swap(usdc, eth)", function!("swap", none, ident!("usdc"), ident!("eth")), vec![]),
+    )]
+  fn test_annotated_statement(input: &'static str, expected: Token, annotations: Vec<Token>) -> Result {
+    let tree = parse(input)?;
+    assert_eq!(tree.len(), 1);
+    tree[0].assert_same_token(&node!(expected));
+
+    assert_eq!(tree[0].annotations.len(), annotations.len());
+    for (i, annotation) in annotations.into_iter().enumerate() {
+      tree[0].annotations[i].assert_same_token(&node!(annotation));
+    }
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_nested_annotated_statement() -> Result {
+    let tree = parse("every 1h {\n  @retry(2)\n  rebalance()\n}")?;
+    let body = tree[0]
+      .token
+      .as_every()
+      .ok_or("expected every")?
+      .body
+      .token
+      .as_body()
+      .unwrap();
+    assert_eq!(body[0].annotations.len(), 1);
+    body[0].annotations[0].assert_same_token(&node!(annotation!("retry", number!(2))));
+    assert_eq!((body[0].annotations[0].line, body[0].annotations[0].column), (2, 3));
+    Ok(())
+  }
+}
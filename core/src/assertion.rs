@@ -0,0 +1,116 @@
+use std::rc::Rc;
+
+use nom::{
+  branch::alt,
+  bytes::complete::tag_no_case,
+  character::complete::space0,
+  combinator::map,
+  sequence::{pair, preceded},
+};
+use nom_tracable::tracable_parser;
+
+use crate::{comma_list, expression, token_failure, Assertion, Node, Result, Span, Token, TokenError};
+
+/// The keyword followed by `(condition)` or `(condition, message)`, any other
+/// number of arguments is an error rather than a call to a function of the same name
+#[tracable_parser]
+fn keyword_and_args(i: Span) -> Result<Span, (Span, Assertion)> {
+  let (rest, (keyword, args)) = pair(
+    alt((tag_no_case("assert"), tag_no_case("require"))),
+    preceded(space0, comma_list('(', expression, ')')),
+  )(i)?;
+  if !(1..=2).contains(&args.len()) {
+    let error = TokenError::AssertionArgsError(keyword.fragment().to_lowercase(), args.len());
+    return Err(token_failure(i, error));
+  }
+
+  let mut args = args.into_iter().map(Rc::new);
+  let assertion = Assertion {
+    condition: args.next().unwrap(),
+    message: args.next(),
+  };
+  Ok((rest, (keyword, assertion)))
+}
+
+/// Guards such as `require(balance(usdc) >= 100, "insufficient USDC")` or
+/// `assert(x > 0)`, with an optional message
+#[tracable_parser]
+pub fn assertion(i: Span) -> Result {
+  map(keyword_and_args, |(keyword, a)| {
+    if keyword.fragment().eq_ignore_ascii_case("assert") {
+      Node::new(Token::Assert(a), &keyword)
+    } else {
+      Node::new(Token::Require(a), &keyword)
+    }
+  })(i)
+}
+
+#[cfg(test)]
+mod test {
+  use crate::test::{info, Result};
+  use crate::*;
+  use nom::combinator::all_consuming;
+  use nom_tracable::TracableInfo;
+  use rstest::rstest;
+  use std::convert::TryFrom;
+
+  #[rstest(input, expected,
+      case(
+        r#"require(balance(usdc) >= 100, "insufficient USDC")"#,
+        assertion!(
+          require,
+          binary_op!(function!("balance", none, ident!("usdc")), ">=", number!(100)),
+          string!("insufficient USDC")
+        )
+      ),
+      case("REQUIRE(ready)", assertion!(require, ident!("ready"))),
+      case(r#"require (x > 0, "m")"#, assertion!(require, binary_op!(ident!("x"), ">", number!(0)), string!("m"))),
+      case("assert (x)", assertion!(assert, ident!("x"))),
+      case("assert(x > 0)", assertion!(assert, binary_op!(ident!("x"), ">", number!(0)))),
+      case(
+        "Assert(\n  x > 0,\n  concat(\"x is \", x)\n)",
+        assertion!(
+          assert,
+          binary_op!(ident!("x"), ">", number!(0)),
+          function!("concat", none, string!("x is "), ident!("x"))
+        )
+      ),
+    )]
+  fn test_assertion(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let (span, node) = assertion(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    node.assert_same_token(&node!(expected));
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("require()"),
+    case("require(a, b, c)"),
+    case("required(a)"),
+    case("assert a")
+  )]
+  fn test_assertion_invalid(input: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(all_consuming(assertion)(span).is_err());
+  }
+
+  #[rstest(
+    input,
+    expected,
+    case("require()", "require takes a condition and an optional message, got 0 arguments"),
+    case(
+      "require(a, b, c)",
+      "require takes a condition and an optional message, got 3 arguments"
+    ),
+    case(
+      "ASSERT (a, b, c)",
+      "assert takes a condition and an optional message, got 3 arguments"
+    )
+  )]
+  fn test_assertion_argument_count(input: &'static str, expected: &str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(matches!(assertion(span), Err(nom::Err::Failure(_))));
+    assert_eq!(parse(input).unwrap_err().to_string(), expected);
+  }
+}
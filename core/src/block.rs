@@ -0,0 +1,236 @@
+use std::rc::Rc;
+
+use nom::{
+  bytes::complete::tag_no_case,
+  character::complete::{multispace0, space0, space1},
+  combinator::{map, opt, verify},
+  sequence::{delimited, preceded, terminated, tuple},
+};
+use nom_tracable::tracable_parser;
+
+use crate::{
+  body, comma_list, expression, identifier, membership_operator, option, Atomic, For, Node, Operator, Result, Span,
+  Token, Try,
+};
+
+/// `atomic { ... }` or `atomic(chain=ethereum) { ... }`, the statements in the
+/// body must all be executed together or not at all
+#[tracable_parser]
+pub fn atomic_block(i: Span) -> Result {
+  map(
+    tuple((
+      tag_no_case("atomic"),
+      opt(preceded(space0, comma_list('(', option, ')'))),
+      multispace0,
+      body,
+    )),
+    |(keyword, options, _, body)| {
+      let atomic = Atomic {
+        options: options.unwrap_or_default(),
+        body: Rc::new(body),
+      };
+      Node::new(Token::Atomic(atomic), &keyword)
+    },
+  )(i)
+}
+
+/// `try { ... } catch err { ... }`, the catch body runs when the try body
+/// fails, with the error bound to `err`
+#[tracable_parser]
+pub fn try_block(i: Span) -> Result {
+  map(
+    tuple((
+      tag_no_case("try"),
+      multispace0,
+      body,
+      multispace0,
+      tag_no_case("catch"),
+      opt(preceded(space1, identifier)),
+      multispace0,
+      body,
+    )),
+    |(keyword, _, body, _, _, error, _, catch)| {
+      let t = Try {
+        body: Rc::new(body),
+        error: error.map(Rc::new),
+        catch: Rc::new(catch),
+      };
+      Node::new(Token::Try(t), &keyword)
+    },
+  )(i)
+}
+
+/// `for token in [eth, btc] { ... }` runs the body once per item, bound to
+/// `token`
+#[tracable_parser]
+pub fn for_loop(i: Span) -> Result {
+  map(
+    tuple((
+      terminated(tag_no_case("for"), space1),
+      identifier,
+      delimited(
+        space1,
+        verify(membership_operator, |op: &Node| {
+          op.token == Token::Operator(Operator::In)
+        }),
+        space1,
+      ),
+      expression,
+      multispace0,
+      body,
+    )),
+    |(keyword, binding, _, iterable, _, body)| {
+      let f = For {
+        binding: Rc::new(binding),
+        iterable: Rc::new(iterable),
+        body: Rc::new(body),
+      };
+      Node::new(Token::For(f), &keyword)
+    },
+  )(i)
+}
+
+#[cfg(test)]
+mod test {
+  use crate::test::{info, Result};
+  use crate::*;
+  use nom::combinator::all_consuming;
+  use nom_tracable::TracableInfo;
+  use rstest::rstest;
+
+  #[rstest(input, expected,
+      case(
+        "atomic { approve(usdc, 100); swap(usdc, eth) }",
+        atomic!(
+          [],
+          body!(
+            function!("approve", none, ident!("usdc"), number!(100)),
+            function!("swap", none, ident!("usdc"), ident!("eth"))
+          )
+        )
+      ),
+      case("ATOMIC {}", atomic!([], body!())),
+      case("atomic() {}", atomic!([], body!())),
+      case(
+        "atomic(chain=ethereum) {\n  approve(usdc, 100)\n  swap(usdc, eth)\n}",
+        atomic!(
+          [opt!("chain", ident!("ethereum"))],
+          body!(
+            function!("approve", none, ident!("usdc"), number!(100)),
+            function!("swap", none, ident!("usdc"), ident!("eth"))
+          )
+        )
+      ),
+      case(
+        "atomic(chain=ethereum, gas=1 gwei){ a() }",
+        atomic!(
+          [opt!("chain", ident!("ethereum")), opt!("gas", amount!(1, "gwei", 1_000_000_000))],
+          body!(function!("a"))
+        )
+      ),
+    )]
+  fn test_atomic_block(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let (span, node) = atomic_block(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    node.assert_same_token(&node!(expected));
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("atomic"),
+    case("atomic(chain=ethereum)"),
+    case("atomic(ethereum) { a() }"),
+    case("atomic { a() b() }")
+  )]
+  fn test_atomic_block_invalid(input: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(all_consuming(atomic_block)(span).is_err());
+  }
+
+  #[rstest(input, expected,
+      case(
+        r#"try { swap(usdc, eth) } catch err { notify("swap failed", err) }"#,
+        try_catch!(
+          body!(function!("swap", none, ident!("usdc"), ident!("eth"))),
+          "err",
+          body!(function!("notify", none, string!("swap failed"), ident!("err")))
+        )
+      ),
+      case("TRY {} CATCH E {}", try_catch!(body!(), "e", body!())),
+      case(
+        "try {\n  a()\n  b()\n}\ncatch {\n  c()\n}",
+        try_catch!(body!(function!("a"), function!("b")), none, body!(function!("c")))
+      ),
+    )]
+  fn test_try_block(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let (span, node) = try_block(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    node.assert_same_token(&node!(expected));
+    Ok(())
+  }
+
+  #[test]
+  fn test_try_block_error_location() -> Result {
+    let (_, node) = try_block(Span::new_extra("try { a() }\ncatch  err {}", info()))?;
+    let error = node.token.as_try().unwrap().error.as_ref().unwrap();
+    assert_eq!(error.token, ident!("err"));
+    assert_eq!((error.line, error.column, error.offset), (2, 8, 19));
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("try { a() }"),
+    case("try { a() } catch"),
+    case("try { a() } catch err"),
+    case("try { a() } catch err, e {}"),
+    case("try a() catch {}")
+  )]
+  fn test_try_block_invalid(input: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(all_consuming(try_block)(span).is_err());
+  }
+
+  #[rstest(input, expected,
+      case(
+        "for token in [eth, btc, sol] { buy(token, 100 USDC) }",
+        for_loop!(
+          "token",
+          list!(ident!("eth"), ident!("btc"), ident!("sol")),
+          body!(function!("buy", none, ident!("token"), amount!(100, "usdc")))
+        )
+      ),
+      case(
+        "FOR T IN tokens() {\n  a(t)\n  b(t)\n}",
+        for_loop!("t", function!("tokens"), body!(function!("a", none, ident!("t")), function!("b", none, ident!("t"))))
+      ),
+      case(
+        "for a in x { for b in y { c(a, b) } }",
+        for_loop!(
+          "a",
+          ident!("x"),
+          body!(for_loop!("b", ident!("y"), body!(function!("c", none, ident!("a"), ident!("b")))))
+        )
+      ),
+    )]
+  fn test_for_loop(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let (span, node) = for_loop(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    node.assert_same_token(&node!(expected));
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("for token in tokens"),
+    case("for token not in tokens { a() }"),
+    case("for token inputs { a() }"),
+    case("for in tokens { a() }"),
+    case("fortoken in tokens { a() }")
+  )]
+  fn test_for_loop_invalid(input: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(all_consuming(for_loop)(span).is_err());
+  }
+}
@@ -1,14 +1,219 @@
-use nom::{bytes::complete::is_not, character::complete::char, combinator::map, sequence::pair};
+use nom::{
+  branch::alt,
+  bytes::complete::{is_not, tag, tag_no_case, take_while, take_while1},
+  character::complete::{char, line_ending, multispace0, space0},
+  combinator::{eof, map, not, opt, peek, recognize},
+  multi::{fold_many0, separated_list1},
+  sequence::{pair, preceded, terminated, tuple},
+};
 
 use nom_tracable::tracable_parser;
+use serde::Serialize;
 
-use crate::{Node, Result, Span, Token};
+use crate::{annotation, grammar_version, since, token_failure, Node, Result, Span, Token, TokenError};
 
+/// Lint rules not to report for a statement, from `# vxl-ignore: rule` or
+/// `# vxl-ignore-next-line`. No rules means every rule is ignored.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Ignore {
+  pub rules: Vec<String>,
+}
+
+impl Ignore {
+  fn merge(self, other: Ignore) -> Ignore {
+    if self.rules.is_empty() || other.rules.is_empty() {
+      return Ignore { rules: Vec::new() };
+    }
+
+    let mut rules = self.rules;
+    rules.extend(other.rules);
+    Ignore { rules }
+  }
+}
+
+/// A comment line meant as a `# vxl-ignore` directive, whether or not it's valid
+#[tracable_parser]
+fn directive_line(i: Span) -> Result<Span, Span> {
+  recognize(pair(
+    tuple((char('#'), space0, tag("vxl-ignore"))),
+    take_while(|c| c != '\n' && c != '\r'),
+  ))(i)
+}
+
+/// A `#` comment. `#!vxl` lines are pragmas, so are never comments, and since
+/// version 2 a malformed `# vxl-ignore` directive is an error rather than a
+/// comment, as the rules it names wouldn't be ignored.
 #[tracable_parser]
 pub fn line_comment(i: Span) -> Result {
-  map(pair(char('#'), is_not("\n\r")), |(_, span): (char, Span)| {
-    Node::new(Token::LineComment(String::from(*span.fragment())), &span)
-  })(i)
+  if grammar_version() >= 2 {
+    if let Ok((_, directive)) = directive_line(i) {
+      if alt((ignore_directive, ignore_next_line_directive))(i).is_err() {
+        let directive = directive.fragment().trim_end().to_string();
+        return Err(token_failure(i, TokenError::DirectiveError(directive)));
+      }
+    }
+  }
+
+  map(
+    preceded(not(tag_no_case("#!vxl")), pair(char('#'), is_not("\n\r"))),
+    |(_, span): (char, Span)| Node::new(Token::LineComment(String::from(*span.fragment())), &span),
+  )(i)
+}
+
+/// A `##` doc comment line, without the `##` and the space following it
+#[tracable_parser]
+pub fn doc_comment(i: Span) -> Result<Span, String> {
+  map(
+    preceded(pair(tag("##"), opt(char(' '))), take_while(|c| c != '\n' && c != '\r')),
+    |span: Span| span.fragment().trim_end().to_string(),
+  )(i)
+}
+
+#[tracable_parser]
+fn ignore_rules(i: Span) -> Result<Span, Vec<String>> {
+  separated_list1(
+    tuple((space0, char(','), space0)),
+    map(
+      take_while1(|c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '/')),
+      |span: Span| span.fragment().to_lowercase(),
+    ),
+  )(i)
+}
+
+/// The end of a directive's line
+#[tracable_parser]
+fn directive_end(i: Span) -> Result<Span, Span> {
+  preceded(space0, peek(alt((recognize(line_ending), eof))))(i)
+}
+
+/// `# vxl-ignore: rule-a, rule-b`, trailing the statement it applies to or on
+/// the line before it
+#[tracable_parser]
+pub fn ignore_directive(i: Span) -> Result<Span, Ignore> {
+  map(
+    terminated(
+      preceded(
+        tuple((char('#'), space0, tag("vxl-ignore"), space0, char(':'), space0)),
+        ignore_rules,
+      ),
+      directive_end,
+    ),
+    |rules| Ignore { rules },
+  )(i)
+}
+
+/// `# vxl-ignore-next-line`, optionally followed by `: rule-a, rule-b`, on the
+/// line before the statement it applies to
+#[tracable_parser]
+pub fn ignore_next_line_directive(i: Span) -> Result<Span, Ignore> {
+  map(
+    terminated(
+      preceded(
+        tuple((char('#'), space0, tag("vxl-ignore-next-line"))),
+        opt(preceded(tuple((space0, char(':'), space0)), ignore_rules)),
+      ),
+      directive_end,
+    ),
+    |rules| Ignore {
+      rules: rules.unwrap_or_default(),
+    },
+  )(i)
+}
+
+/// Something that may precede a statement
+#[derive(Debug, Clone)]
+enum Prefix {
+  Docs(String),
+  Ignore(Ignore),
+  Annotation(Node),
+  Comment,
+}
+
+/// Everything preceding a statement, to be attached to it
+#[derive(Debug, Clone, Default)]
+struct Prefixes {
+  docs: Vec<String>,
+  ignore: Option<Ignore>,
+  annotations: Vec<Node>,
+}
+
+impl Prefixes {
+  fn push(mut self, prefix: Prefix) -> Self {
+    match prefix {
+      Prefix::Docs(docs) => self.docs.push(docs),
+      Prefix::Ignore(ignore) => self.ignore = merge_ignores(self.ignore, Some(ignore)),
+      Prefix::Annotation(annotation) => self.annotations.push(annotation),
+      Prefix::Comment => {}
+    }
+    self
+  }
+}
+
+fn merge_ignores(a: Option<Ignore>, b: Option<Ignore>) -> Option<Ignore> {
+  match (a, b) {
+    (Some(a), Some(b)) => Some(a.merge(b)),
+    (a, b) => a.or(b),
+  }
+}
+
+/// A doc comment, `# vxl-ignore` directive or plain comment on its own line
+#[tracable_parser]
+fn line_prefix(i: Span) -> Result<Span, Prefix> {
+  terminated(
+    alt((
+      map(doc_comment, Prefix::Docs),
+      map(ignore_next_line_directive, Prefix::Ignore),
+      map(ignore_directive, Prefix::Ignore),
+      map(line_comment, |_| Prefix::Comment),
+    )),
+    tuple((space0, line_ending, multispace0)),
+  )(i)
+}
+
+/// An annotation, which may share its line with the statement
+#[tracable_parser]
+fn annotation_prefix(i: Span) -> Result<Span, Prefix> {
+  map(terminated(annotation, multispace0), Prefix::Annotation)(i)
+}
+
+/// Attaches the prefixes parsed by `prefix` preceding what `parser` parses, as
+/// well as a trailing `# vxl-ignore: rule`
+fn prefixed<'a>(
+  mut prefix: impl FnMut(Span<'a>) -> Result<'a, Span<'a>, Prefix>,
+  mut parser: impl FnMut(Span<'a>) -> Result<'a>,
+) -> impl FnMut(Span<'a>) -> Result<'a> {
+  move |i: Span<'a>| {
+    let (i, prefixes) = fold_many0(since(2, &mut prefix), Prefixes::default(), Prefixes::push)(i)?;
+    let (i, node) = parser(i)?;
+    let (i, trailing) = opt(since(
+      2,
+      preceded(tuple((space0, opt(char(';')), space0)), ignore_directive),
+    ))(i)?;
+
+    let docs = if prefixes.docs.is_empty() {
+      node.docs
+    } else {
+      Some(prefixes.docs.join("\n"))
+    };
+    let node = Node {
+      docs,
+      ignore: merge_ignores(prefixes.ignore, trailing),
+      annotations: prefixes.annotations,
+      ..node
+    };
+    Ok((i, node))
+  }
+}
+
+/// Attaches the doc comments and `# vxl-ignore` directives around what `parser`
+/// parses, the preceding ones in any order and mixed with plain comments
+pub(crate) fn documented<'a>(parser: impl FnMut(Span<'a>) -> Result<'a>) -> impl FnMut(Span<'a>) -> Result<'a> {
+  prefixed(line_prefix, parser)
+}
+
+/// Like `documented`, also attaching the preceding annotations
+pub(crate) fn annotated<'a>(parser: impl FnMut(Span<'a>) -> Result<'a>) -> impl FnMut(Span<'a>) -> Result<'a> {
+  prefixed(alt((line_prefix, annotation_prefix)), parser)
 }
 
 #[cfg(test)]
@@ -33,4 +238,197 @@ mod test {
 
     Ok(())
   }
+
+  #[rstest(
+    input,
+    expected,
+    case("## Buys the dip", "Buys the dip"),
+    case("##no space  ", "no space"),
+    case("##", ""),
+    case("##   indented", "  indented")
+  )]
+  fn test_doc_comment(input: &'static str, expected: &str, info: TracableInfo) -> Result {
+    let input = Span::new_extra(input, info);
+    let (span, docs) = doc_comment(input)?;
+    assert!(span.fragment().is_empty());
+    assert_eq!(docs, expected);
+
+    Ok(())
+  }
+
+  #[rstest(input, expected, docs,
+    case("## Swaps\nswap(usdc, eth)", function!("swap", none, ident!("usdc"), ident!("eth")), Some("Swaps")),
+    case(
+      "## Buys the dip.\n##\n## Uses `amount` USDC.\ndef buy_dip(amount) { buy(eth, amount) }",
+      def!("buy_dip", [ident!("amount")], body!(function!("buy", none, ident!("eth"), ident!("amount")))),
+      Some("Buys the dip.\n\nUses `amount` USDC.")
+    ),
+    case("## Amount to buy\nparam amount = 100", param!("amount", none, number!(100)), Some("Amount to buy")),
+    case("## Retried\n@retry(3)\nswap()", function!("swap"), Some("Retried")),
+    case("## Retried\n@retry(3)\n# vxl-ignore-next-line\nswap()", function!("swap"), Some("Retried")),
+    case("@retry(3)\n## Retried\nswap()", function!("swap"), Some("Retried")),
+    case("## Swaps\n# a note\n##\n## twice\nswap()", function!("swap"), Some("Swaps\n\ntwice")),
+    case("# Not docs\nswap()", function!("swap"), None),
+    case("#!vxl 1\n## Not docs in v1\nswap()", function!("swap"), None),
+  )]
+  fn test_documented(input: &'static str, expected: Token, docs: Option<&str>) -> Result {
+    let tree = parse(input)?;
+    assert_eq!(tree.len(), 1);
+    tree[0].assert_same_token(&node!(expected));
+    assert_eq!(tree[0].docs.as_deref(), docs);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_documented_in_body() -> Result {
+    let tree = parse("when ready {\n  ## Sells\n  sell(eth)\n  ## Dangling\n}")?;
+    let body = tree[0]
+      .token
+      .as_when()
+      .ok_or("expected when")?
+      .body
+      .token
+      .as_body()
+      .unwrap();
+    assert_eq!(body.len(), 1);
+    assert_eq!(body[0].docs.as_deref(), Some("Sells"));
+    assert_eq!((body[0].line, body[0].column), (3, 3));
+
+    Ok(())
+  }
+
+  #[rstest(input, expected,
+    case("# vxl-ignore: unused-param", vec!["unused-param"]),
+    case("#vxl-ignore:a,B , c/d", vec!["a", "b", "c/d"]),
+  )]
+  fn test_ignore_directive(input: &'static str, expected: Vec<&str>, info: TracableInfo) -> Result {
+    let (span, ignore) = ignore_directive(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    assert_eq!(ignore.rules, expected);
+
+    Ok(())
+  }
+
+  #[rstest(input, expected,
+    case("# vxl-ignore-next-line", vec![]),
+    case("# vxl-ignore-next-line: slippage, gas", vec!["slippage", "gas"]),
+  )]
+  fn test_ignore_next_line_directive(input: &'static str, expected: Vec<&str>, info: TracableInfo) -> Result {
+    let (span, ignore) = ignore_next_line_directive(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    assert_eq!(ignore.rules, expected);
+
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("# vxl-ignore"),
+    case("# vxl-ignore:"),
+    case("# vxl-ignore: rule because"),
+    case("# vxl-ignores: rule")
+  )]
+  fn test_ignore_directive_invalid(input: &'static str, info: TracableInfo) {
+    assert!(ignore_directive(Span::new_extra(input, info)).is_err());
+  }
+
+  #[rstest(input, expected, ignore,
+    case("swap() # vxl-ignore: slippage", function!("swap"), Some(vec!["slippage"])),
+    case("# vxl-ignore-next-line\nparam amount", param!("amount", none, none), Some(vec![])),
+    case("param x # vxl-ignore: unused", param!("x", none, none), Some(vec!["unused"])),
+    case(
+      "# vxl-ignore-next-line: a\n## Amount\nparam x # vxl-ignore: b",
+      param!("x", none, none),
+      Some(vec!["a", "b"])
+    ),
+    case("# vxl-ignore-next-line\n## Docs\nswap()", function!("swap"), Some(vec![])),
+    case("import \"x.vxl\" # vxl-ignore: unused", import!("x.vxl"), Some(vec!["unused"])),
+    case("swap(); # vxl-ignore: a, b", function!("swap"), Some(vec!["a", "b"])),
+    case("# vxl-ignore-next-line\nswap()", function!("swap"), Some(vec![])),
+    case("# vxl-ignore: slippage\nswap()", function!("swap"), Some(vec!["slippage"])),
+    case("# vxl-ignore: a\n## Amount\nparam x", param!("x", none, none), Some(vec!["a"])),
+    case(
+      "# vxl-ignore: a\n# vxl-ignore-next-line: b\nswap() # vxl-ignore: c",
+      function!("swap"),
+      Some(vec!["a", "b", "c"])
+    ),
+    case(
+      "## Docs\n# vxl-ignore-next-line: a\n@retry(3)\nswap() # vxl-ignore: b",
+      function!("swap"),
+      Some(vec!["a", "b"])
+    ),
+    case("swap() # a comment", function!("swap"), None),
+    case("# a comment\nswap()", function!("swap"), None),
+    case("#!vxl 1\nswap() # vxl-ignore: a", function!("swap"), None),
+  )]
+  fn test_ignorable(input: &'static str, expected: Token, ignore: Option<Vec<&str>>) -> Result {
+    let tree = parse(input)?;
+    assert_eq!(tree.len(), 1);
+    tree[0].assert_same_token(&node!(expected));
+    let rules = tree[0]
+      .ignore
+      .as_ref()
+      .map(|i| i.rules.iter().map(String::as_str).collect::<Vec<_>>());
+    assert_eq!(rules, ignore);
+
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    directive,
+    case(
+      "swap() # vxl-ignore: slippage -- pool is deep",
+      "# vxl-ignore: slippage -- pool is deep"
+    ),
+    case("# vxl-ignore: slippage because\nswap()", "# vxl-ignore: slippage because"),
+    case("# vxl-ignore\nswap()", "# vxl-ignore"),
+    case("swap() # vxl-ignores: a", "# vxl-ignores: a"),
+    case("# vxl-ignore-next-line: a b\nparam x", "# vxl-ignore-next-line: a b"),
+    case("when ready {\n  # vxl-ignore:\n  swap()\n}", "# vxl-ignore:")
+  )]
+  fn test_ignorable_malformed(input: &'static str, directive: &str) {
+    let error = parse(input).unwrap_err();
+    assert_eq!(
+      error.to_string(),
+      format!(
+        "invalid directive {:?}, expected `# vxl-ignore: rule, ...` or `# vxl-ignore-next-line`",
+        directive
+      )
+    );
+  }
+
+  #[test]
+  fn test_ignorable_malformed_v1() -> Result {
+    let program = parse_program("#!vxl 1\nswap() # vxl-ignore: slippage -- pool is deep")?;
+    assert_eq!(program.tree.len(), 1);
+    assert!(program.tree[0].ignore.is_none());
+    Ok(())
+  }
+
+  #[test]
+  fn test_ignorable_in_body() -> Result {
+    let tree = parse("when ready {\n  # vxl-ignore-next-line\n  a()\n  b() # vxl-ignore: x\n  c()\n}")?;
+    let body = tree[0]
+      .token
+      .as_when()
+      .ok_or("expected when")?
+      .body
+      .token
+      .as_body()
+      .unwrap();
+    assert_eq!(body.len(), 3);
+    assert_eq!(body[0].ignore, Some(Ignore { rules: vec![] }));
+    assert_eq!(
+      body[1].ignore,
+      Some(Ignore {
+        rules: vec!["x".into()]
+      })
+    );
+    assert_eq!(body[2].ignore, None);
+    assert!(tree[0].ignore.is_none());
+
+    Ok(())
+  }
 }
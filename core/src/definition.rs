@@ -0,0 +1,192 @@
+use std::{cell::Cell, rc::Rc};
+
+use nom::{
+  branch::alt,
+  bytes::complete::tag_no_case,
+  character::complete::{multispace0, satisfy, space0, space1},
+  combinator::{map, not, opt},
+  sequence::{preceded, terminated, tuple},
+};
+use nom_tracable::tracable_parser;
+
+use crate::{
+  body, comma_list, documented, expression, identifier, option, token_failure, typed_param, valid_ident_char_a,
+  Definition, Node, Result, Span, Token, TokenError,
+};
+
+thread_local! {
+  static DEFINITION_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Marks a definition body as being parsed for as long as it's alive, so that
+/// `return` is only accepted inside one
+struct InDefinition;
+
+impl InDefinition {
+  fn enter() -> Self {
+    DEFINITION_DEPTH.with(|d| d.set(d.get() + 1));
+    InDefinition
+  }
+}
+
+impl Drop for InDefinition {
+  fn drop(&mut self) {
+    DEFINITION_DEPTH.with(|d| d.set(d.get() - 1));
+  }
+}
+
+#[tracable_parser]
+fn definition_param(i: Span) -> Result {
+  alt((typed_param, option, identifier))(i)
+}
+
+#[tracable_parser]
+fn definition_body(i: Span) -> Result {
+  let _in_definition = InDefinition::enter();
+  body(i)
+}
+
+/// `def name(param, opt=default) { ... }` defines a reusable sequence of
+/// statements; names are lowercased like identifiers
+#[tracable_parser]
+pub fn definition(i: Span) -> Result {
+  map(
+    tuple((
+      terminated(tag_no_case("def"), space1),
+      identifier,
+      comma_list('(', documented(definition_param), ')'),
+      multispace0,
+      definition_body,
+    )),
+    |(keyword, name, params, _, body)| {
+      let def = Definition {
+        name: Rc::new(name),
+        params,
+        body: Rc::new(body),
+      };
+      Node::new(Token::Def(def), &keyword)
+    },
+  )(i)
+}
+
+/// `return` with an optional value, only valid inside a definition. As a
+/// keyword it's never a function call, so `return(1)` is a return as well.
+#[tracable_parser]
+pub fn return_statement(i: Span) -> Result {
+  let (rest, keyword) = terminated(tag_no_case("return"), not(satisfy(valid_ident_char_a)))(i)?;
+  if DEFINITION_DEPTH.with(|d| d.get()) == 0 {
+    return Err(token_failure(i, TokenError::ReturnError));
+  }
+
+  map(opt(preceded(space0, expression)), move |value| {
+    Node::new(Token::Return(value.map(Rc::new)), &keyword)
+  })(rest)
+}
+
+#[cfg(test)]
+mod test {
+  use crate::test::{info, Result};
+  use crate::*;
+  use nom::combinator::all_consuming;
+  use nom_tracable::TracableInfo;
+  use rstest::rstest;
+  use std::convert::TryFrom;
+
+  #[rstest(input, expected,
+      case(
+        "def buy_dip(token, amount=100 USDC) { buy(token, amount) }",
+        def!(
+          "buy_dip",
+          [ident!("token"), opt!("amount", amount!(100, "usdc"))],
+          body!(function!("buy", none, ident!("token"), ident!("amount")))
+        )
+      ),
+      case("DEF Noop() {}", def!("noop", [], body!())),
+      case(
+        "def f(a: number, b: list<address> = [], c=1) {}",
+        def!(
+          "f",
+          [
+            param!("a", type_expr!("number"), none),
+            param!("b", type_expr!("list", [type_expr!("address")]), list!()),
+            opt!("c", number!(1))
+          ],
+          body!()
+        )
+      ),
+      case(
+        "def double(x) {\n  return x * 2\n}",
+        def!("double", [ident!("x")], body!(ret!(binary_op!(ident!("x"), "*", number!(2)))))
+      ),
+      case(
+        "def check(\n  x,\n  min=1\n) {\n  if x < min { return }\n  return(x)\n}",
+        def!(
+          "check",
+          [ident!("x"), opt!("min", number!(1))],
+          body!(
+            conditional!(binary_op!(ident!("x"), "<", ident!("min")), body!(ret!())),
+            ret!(ident!("x"))
+          )
+        )
+      ),
+    )]
+  fn test_definition(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let (span, node) = definition(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    node.assert_same_token(&node!(expected));
+    Ok(())
+  }
+
+  #[test]
+  fn test_definition_param_docs() -> Result {
+    let input = "def buy(\n  ## What to buy\n  token,\n  # not docs\n  amount = 100, # defaults\n  ## Max slippage\n  ## in percent\n  slippage: number\n) {}";
+    let (_, node) = definition(Span::new_extra(input, info()))?;
+    let params = &node.token.as_def().ok_or("expected a definition")?.params;
+    let docs: Vec<_> = params.iter().map(|p| p.docs.as_deref()).collect();
+    assert_eq!(docs, [Some("What to buy"), None, Some("Max slippage\nin percent")]);
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("def f()"),
+    case("def f { a() }"),
+    case("def f(a b) { a() }"),
+    case("deff() { a() }"),
+    case("def f() { return 1 2 }")
+  )]
+  fn test_definition_invalid(input: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(all_consuming(definition)(span).is_err());
+  }
+
+  #[rstest(input, case("return"), case("return 1"))]
+  fn test_return_outside_definition(input: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(matches!(return_statement(span), Err(nom::Err::Failure(_))));
+  }
+
+  #[rstest(
+    input,
+    case("return 1"),
+    case("return(1)"),
+    case("when ready {\n  return\n}"),
+    case("def f() { g() }\nreturn f()")
+  )]
+  fn test_return_outside_definition_message(input: &'static str) {
+    assert_eq!(parse(input).unwrap_err().to_string(), "return outside of a definition");
+  }
+
+  #[test]
+  fn test_return_call_v1() -> Result {
+    let program = parse_program("#!vxl 1\nreturn(1)")?;
+    program.tree[0].assert_same_token(&node!(function!("return", none, number!(1))));
+    Ok(())
+  }
+
+  #[rstest(input, case("returns()"), case("return_value"))]
+  fn test_return_prefix(input: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(matches!(return_statement(span), Err(nom::Err::Error(_))));
+  }
+}
@@ -0,0 +1,68 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use crate::Setting;
+
+/// Denominations map units to their number of decimals relative to a base unit,
+/// so amounts such as `1.5 ether` can be scaled exactly into base units at parse time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Denominations(HashMap<String, u32>);
+
+impl Default for Denominations {
+  fn default() -> Self {
+    let mut d = Denominations(HashMap::new());
+    d.register("wei", 0);
+    d.register("gwei", 9);
+    d.register("ether", 18);
+    d
+  }
+}
+
+impl Denominations {
+  /// Registers (or replaces) a unit, matched case-insensitively like identifiers
+  pub fn register(&mut self, unit: &str, decimals: u32) -> &mut Self {
+    self.0.insert(unit.to_lowercase(), decimals);
+    self
+  }
+
+  pub fn decimals(&self, unit: &str) -> Option<u32> {
+    self.0.get(&unit.to_lowercase()).copied()
+  }
+}
+
+thread_local! {
+  static DENOMINATIONS: RefCell<Denominations> = RefCell::new(Denominations::default());
+}
+
+/// Runs `f` with `denominations` as the units the parser scales amounts by
+pub fn with_denominations<T>(denominations: Denominations, f: impl FnOnce() -> T) -> T {
+  let _denominations = Setting::set(&DENOMINATIONS, denominations);
+  f()
+}
+
+pub fn denomination_decimals(unit: &str) -> Option<u32> {
+  DENOMINATIONS.with(|d| d.borrow().decimals(unit))
+}
+
+#[cfg(test)]
+mod test {
+  use crate::*;
+
+  #[test]
+  fn test_default_denominations() {
+    let d = Denominations::default();
+    assert_eq!(d.decimals("wei"), Some(0));
+    assert_eq!(d.decimals("GWEI"), Some(9));
+    assert_eq!(d.decimals("ether"), Some(18));
+    assert_eq!(d.decimals("usdc"), None);
+  }
+
+  #[test]
+  fn test_with_denominations() {
+    let mut d = Denominations::default();
+    d.register("USDC", 6);
+
+    assert_eq!(denomination_decimals("usdc"), None);
+    assert_eq!(with_denominations(d, || denomination_decimals("usdc")), Some(6));
+    assert_eq!(denomination_decimals("usdc"), None);
+  }
+}
@@ -0,0 +1,80 @@
+use nom::{
+  bytes::complete::take_while1,
+  character::complete::{char, space0},
+  combinator::{map, not},
+  multi::many0,
+  sequence::{pair, preceded, terminated},
+};
+use nom_tracable::tracable_parser;
+
+use crate::{valid_ident_char_a, Handle, Node, Result, Span, Token};
+
+#[tracable_parser]
+fn handle_segment(i: Span) -> Result<Span, String> {
+  map(take_while1(valid_ident_char_a), |span: Span| {
+    span.fragment().to_lowercase()
+  })(i)
+}
+
+/// Named accounts such as `@treasury` or `@alice.hot`, resolved to addresses
+/// outside of the script. The path stops before a segment that is called, so
+/// `@treasury.balance(eth)` is a call on `@treasury`
+#[tracable_parser]
+pub fn handle(i: Span) -> Result {
+  map(
+    preceded(
+      char('@'),
+      pair(
+        handle_segment,
+        many0(preceded(
+          char('.'),
+          terminated(handle_segment, not(pair(space0, char('(')))),
+        )),
+      ),
+    ),
+    |(name, path)| Node::new(Token::Handle(Handle { name, path }), &i),
+  )(i)
+}
+
+#[cfg(test)]
+mod test {
+  use crate::test::{info, Result};
+  use crate::*;
+  use nom_tracable::TracableInfo;
+  use rstest::rstest;
+
+  #[rstest(input, expected,
+      case("@treasury", handle!("treasury")),
+      case("@Treasury", handle!("treasury")),
+      case("@alice.hot", handle!("alice", "hot")),
+      case("@team.ops.multisig_2", handle!("team", "ops", "multisig_2")),
+    )]
+  fn test_handle(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let (span, node) = handle(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    assert_eq!(node.token, expected);
+
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    rest,
+    case("@alice.", "."),
+    case("@alice..hot", "..hot"),
+    case("@alice hot", " hot"),
+    case("@treasury.balance(eth)", ".balance(eth)"),
+    case("@team.ops.balance (eth)", ".balance (eth)")
+  )]
+  fn test_handle_partial(input: &'static str, rest: &'static str, info: TracableInfo) -> Result {
+    let (span, _) = handle(Span::new_extra(input, info))?;
+    assert_eq!(*span.fragment(), rest);
+
+    Ok(())
+  }
+
+  #[rstest(input, case("@"), case("@.hot"), case("treasury"), case("@ treasury"))]
+  fn test_handle_invalid(input: &'static str, info: TracableInfo) {
+    assert!(handle(Span::new_extra(input, info)).is_err());
+  }
+}
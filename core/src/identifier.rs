@@ -4,7 +4,14 @@ use nom::{bytes::complete::take_while1, combinator::map};
 
 use nom_tracable::tracable_parser;
 
-fn valid_ident_char_a(c: char) -> bool {
+/// Words with a meaning of their own in the grammar, which can't be units and
+/// can't be called with a space before the arguments
+pub(crate) const KEYWORDS: [&str; 23] = [
+  "and", "or", "not", "in", "xor", "between", "if", "else", "match", "when", "every", "atomic", "try", "catch", "for",
+  "def", "return", "assert", "require", "import", "as", "param", "pragma",
+];
+
+pub(crate) fn valid_ident_char_a(c: char) -> bool {
   c.is_ascii_alphanumeric() || matches!(c, '_')
 }
 
@@ -0,0 +1,329 @@
+use std::{
+  collections::{BTreeMap, HashMap},
+  error::Error,
+  fs,
+  rc::Rc,
+};
+
+use nom::{
+  bytes::complete::tag_no_case,
+  character::complete::space1,
+  combinator::{map, opt},
+  sequence::{preceded, terminated, tuple},
+};
+use nom_tracable::tracable_parser;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+  identifier, parse_program, string, with_source, Import, Metadata, Node, Result, SResult, Span, Token, Tree,
+};
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+  #[error("unable to load {0}: {1}")]
+  LoadError(String, String),
+
+  #[error("unable to parse {0}: {1}")]
+  ParseError(String, String),
+
+  #[error("import cycle: {0}")]
+  CycleError(String),
+}
+
+/// `import "lib/common.vxl"` or `import "x.vxl" as x`, only allowed at the top
+/// level of a file
+#[tracable_parser]
+pub fn import_statement(i: Span) -> Result {
+  map(
+    tuple((
+      terminated(tag_no_case("import"), space1),
+      string,
+      opt(preceded(tuple((space1, tag_no_case("as"), space1)), identifier)),
+    )),
+    |(keyword, path, alias)| {
+      let import = Import {
+        path: path.token.as_string().unwrap_or_default().to_string(),
+        alias: alias.map(Rc::new),
+        resolved: None,
+      };
+      Node::new(Token::Import(import), &keyword)
+    },
+  )(i)
+}
+
+/// Loaders resolve and read imported files, so that sources can come from
+/// somewhere other than the filesystem
+pub trait Loader {
+  /// Resolves `path` as imported by the file `from`, or as the entry file when
+  /// `from` is `None`. Resolved paths identify files when detecting cycles.
+  fn resolve(&self, from: Option<&str>, path: &str) -> String {
+    resolve_relative(from, path)
+  }
+
+  fn load(&self, path: &str) -> SResult<String, Box<dyn Error>>;
+}
+
+/// Loads imports from the filesystem
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FileLoader;
+
+impl Loader for FileLoader {
+  fn load(&self, path: &str) -> SResult<String, Box<dyn Error>> {
+    Ok(fs::read_to_string(path)?)
+  }
+}
+
+/// Loads imports from in-memory sources keyed by path
+impl Loader for HashMap<String, String> {
+  fn load(&self, path: &str) -> SResult<String, Box<dyn Error>> {
+    self.get(path).cloned().ok_or_else(|| "no such file".into())
+  }
+}
+
+/// Joins `path` onto the directory of `from`, collapsing `.` and `..` segments.
+/// Paths starting with `/` are left absolute.
+pub fn resolve_relative(from: Option<&str>, path: &str) -> String {
+  let mut segments: Vec<&str> = Vec::new();
+  if let (Some(from), false) = (from, path.starts_with('/')) {
+    segments.extend(from.split('/'));
+    // drop the importing file's name, leaving its directory
+    segments.pop();
+  }
+
+  for segment in path.split('/') {
+    match segment {
+      "." => {}
+      ".." => match segments.last() {
+        // `..` can't go above the root
+        Some(&"") => {}
+        Some(s) if *s != ".." => {
+          segments.pop();
+        }
+        _ => segments.push(segment),
+      },
+      "" if !segments.is_empty() => {}
+      _ => segments.push(segment),
+    }
+  }
+
+  segments.join("/")
+}
+
+/// A program parsed from an entry file and everything it imports
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Bundle {
+  /// The resolved path of the entry file
+  pub entry: String,
+  /// The metadata of each file, e.g. its grammar version, by resolved path
+  pub files: BTreeMap<String, Metadata>,
+  pub tree: Tree,
+}
+
+/// Parses the file at `path` and, recursively, everything it imports into one
+/// program. The statements of an imported file come before the import, each
+/// file is only included once, and every node knows the file it was parsed from.
+pub fn parse_file(path: &str, loader: &dyn Loader) -> SResult<Bundle, ImportError> {
+  let mut bundle = Bundle {
+    entry: loader.resolve(None, path),
+    files: BTreeMap::new(),
+    tree: Tree::new(),
+  };
+  let entry = bundle.entry.clone();
+  load_file(&entry, loader, &mut Vec::new(), &mut bundle)?;
+  Ok(bundle)
+}
+
+fn load_file(
+  path: &str,
+  loader: &dyn Loader,
+  loading: &mut Vec<String>,
+  bundle: &mut Bundle,
+) -> SResult<(), ImportError> {
+  if loading.iter().any(|p| p == path) {
+    let mut cycle = loading.clone();
+    cycle.push(path.to_string());
+    return Err(ImportError::CycleError(cycle.join(" -> ")));
+  }
+  if bundle.files.contains_key(path) {
+    return Ok(());
+  }
+
+  let source = loader
+    .load(path)
+    .map_err(|e| ImportError::LoadError(path.to_string(), e.to_string()))?;
+  let program = with_source(path, || parse_program(&source).map_err(|e| e.to_string()))
+    .map_err(|e| ImportError::ParseError(path.to_string(), e))?;
+  bundle.files.insert(path.to_string(), program.metadata);
+
+  loading.push(path.to_string());
+  for mut node in program.tree {
+    if let Token::Import(import) = &mut node.token {
+      let resolved = loader.resolve(Some(path), &import.path);
+      load_file(&resolved, loader, loading, bundle)?;
+      import.resolved = Some(resolved);
+    }
+    bundle.tree.push(node);
+  }
+  loading.pop();
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use crate::test::{info, Result};
+  use crate::*;
+  use nom_tracable::TracableInfo;
+  use rstest::rstest;
+  use std::collections::HashMap;
+
+  #[rstest(input, expected,
+      case(r#"import "lib/common.vxl""#, import!("lib/common.vxl")),
+      case(r#"IMPORT "x.vxl" AS X"#, import!("x.vxl", "x")),
+    )]
+  fn test_import_statement(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let (span, node) = import_statement(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    node.assert_same_token(&node!(expected));
+    Ok(())
+  }
+
+  #[rstest(
+    from,
+    path,
+    expected,
+    case(None, "main.vxl", "main.vxl"),
+    case(Some("main.vxl"), "lib/common.vxl", "lib/common.vxl"),
+    case(Some("src/main.vxl"), "./lib/common.vxl", "src/lib/common.vxl"),
+    case(Some("src/lib/a.vxl"), "../b.vxl", "src/b.vxl"),
+    case(Some("a.vxl"), "../b.vxl", "../b.vxl"),
+    case(Some("/src/main.vxl"), "x.vxl", "/src/x.vxl"),
+    case(Some("src/main.vxl"), "/x.vxl", "/x.vxl"),
+    case(Some("/main.vxl"), "../x.vxl", "/x.vxl")
+  )]
+  fn test_resolve_relative(from: Option<&str>, path: &str, expected: &str) {
+    assert_eq!(resolve_relative(from, path), expected);
+  }
+
+  fn sources(files: &[(&str, &str)]) -> HashMap<String, String> {
+    files.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+  }
+
+  fn sources_of(tree: &[Node]) -> Vec<&str> {
+    tree
+      .iter()
+      .map(|node| node.source.as_deref().unwrap_or_default())
+      .collect()
+  }
+
+  #[test]
+  fn test_parse_file() -> Result {
+    let loader = sources(&[
+      ("main.vxl", "import \"lib/common.vxl\" as common\nfun()"),
+      ("lib/common.vxl", "import \"./util.vxl\"\n\nhelper()"),
+      ("lib/util.vxl", "util()"),
+    ]);
+
+    let bundle = parse_file("main.vxl", &loader)?;
+    let tree = &bundle.tree;
+    assert_eq!(
+      sources_of(tree),
+      [
+        "lib/util.vxl",
+        "lib/common.vxl",
+        "lib/common.vxl",
+        "main.vxl",
+        "main.vxl"
+      ]
+    );
+
+    tree[0].assert_same_token(&node!(function!("util")));
+    assert_eq!(
+      tree[0].token.as_function().unwrap().name.source.as_deref(),
+      Some("lib/util.vxl")
+    );
+    tree[1].assert_same_token(&node!(import!("./util.vxl" => "lib/util.vxl")));
+    tree[2].assert_same_token(&node!(function!("helper")));
+    assert_eq!(tree[2].line, 3);
+    tree[3].assert_same_token(&node!(import!("lib/common.vxl", "common" => "lib/common.vxl")));
+    tree[4].assert_same_token(&node!(function!("fun")));
+
+    // the nodes of `common` are those parsed from the file it resolved to
+    let common = tree[3].token.as_import().ok_or("expected import")?;
+    let resolved = common.resolved.as_deref();
+    let nodes: Vec<usize> = (0..tree.len())
+      .filter(|&i| tree[i].source.as_deref() == resolved)
+      .collect();
+    assert_eq!(nodes, [1, 2]);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_parse_file_versions() -> Result {
+    let loader = sources(&[
+      ("src/main.vxl", "import \"lib.vxl\"\nfun(1,)"),
+      ("src/lib.vxl", "#!vxl 1\nfun()"),
+    ]);
+
+    let bundle = parse_file("src/main.vxl", &loader)?;
+    assert_eq!(bundle.entry, "src/main.vxl");
+    let versions: Vec<(&str, u32)> = bundle
+      .files
+      .iter()
+      .map(|(path, metadata)| (path.as_str(), metadata.version))
+      .collect();
+    assert_eq!(versions, [("src/lib.vxl", 1), ("src/main.vxl", LATEST_VERSION)]);
+
+    // each file is parsed with its own version
+    let loader = sources(&[("main.vxl", "import \"lib.vxl\""), ("lib.vxl", "#!vxl 1\nfun(1,)")]);
+    assert!(parse_file("main.vxl", &loader).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_parse_file_cycle() {
+    let loader = sources(&[
+      ("a.vxl", "import \"b.vxl\""),
+      ("b.vxl", "import \"lib/c.vxl\""),
+      ("lib/c.vxl", "import \"../a.vxl\""),
+    ]);
+
+    match parse_file("a.vxl", &loader) {
+      Err(ImportError::CycleError(cycle)) => assert_eq!(cycle, "a.vxl -> b.vxl -> lib/c.vxl -> a.vxl"),
+      other => panic!("expected a cycle error, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn test_parse_file_shared_import() -> Result {
+    let loader = sources(&[
+      ("main.vxl", "import \"a.vxl\"\nimport \"b.vxl\""),
+      ("a.vxl", "import \"common.vxl\""),
+      ("b.vxl", "import \"common.vxl\""),
+      ("common.vxl", "fun()"),
+    ]);
+
+    let tree = parse_file("main.vxl", &loader)?.tree;
+    assert_eq!(
+      sources_of(&tree),
+      ["common.vxl", "a.vxl", "main.vxl", "b.vxl", "main.vxl"]
+    );
+    Ok(())
+  }
+
+  #[rstest(files, expected,
+      case(&[("main.vxl", "import \"missing.vxl\"")], "unable to load missing.vxl: no such file"),
+      case(&[("main.vxl", "import \"bad.vxl\""), ("bad.vxl", "fun() fun()")], "unable to parse bad.vxl"),
+    )]
+  fn test_parse_file_invalid(files: &[(&str, &str)], expected: &str) {
+    let error = parse_file("main.vxl", &sources(files)).unwrap_err();
+    assert!(error.to_string().starts_with(expected), "{}", error);
+  }
+
+  #[test]
+  fn test_import_outside_top_level() {
+    assert!(parse("when ready { import \"x.vxl\" }").is_err());
+  }
+}
@@ -5,42 +5,72 @@ use tracer::get_tracer;
 
 #[macro_use]
 mod address;
+mod annotation;
+mod assertion;
+mod block;
 mod boolean;
 mod collection;
 mod comment;
+mod definition;
+mod denomination;
+mod handle;
 mod identifier;
+mod import;
 mod list;
 mod literal;
 mod n;
 mod node;
 mod numeric;
 mod operation;
+mod pair;
+mod param;
+mod pattern;
+mod pragma;
 mod result;
+mod setting;
 mod string;
+mod time;
 mod tokens;
+mod trigger;
+mod types;
 
 pub use address::*;
+pub use annotation::*;
+pub use assertion::*;
+pub use block::*;
 pub use boolean::*;
 pub use collection::*;
 pub use comment::*;
+pub use definition::*;
+pub use denomination::*;
+pub use handle::*;
 pub use identifier::*;
+pub use import::*;
 pub use list::*;
 pub use literal::*;
 pub use n::*;
 pub use node::*;
 pub use numeric::*;
 pub use operation::*;
+pub use pair::*;
+pub use param::*;
+pub use pattern::*;
+pub use pragma::*;
 pub use result::*;
+use setting::*;
 pub use string::*;
+pub use time::*;
 pub use tokens::*;
+pub use trigger::*;
+pub use types::*;
 
 use nom::{
   branch::alt,
   bytes::complete::{tag, tag_no_case, take},
-  character::complete::{char, line_ending, multispace0, newline, space0},
-  combinator::{all_consuming, complete, eof, map, opt, recognize},
+  character::complete::{char, line_ending, multispace0, newline, space0, space1},
+  combinator::{all_consuming, complete, eof, map, not, opt, peek, recognize, verify},
   error::ErrorKind,
-  multi::{fold_many0, fold_many1, many0, many1},
+  multi::{fold_many0, many1},
   sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
   Err,
 };
@@ -50,45 +80,44 @@ use nom_tracable::tracable_parser;
 
 #[tracable_parser]
 fn function_arg(i: Span) -> Result {
-  alt((option, operation, expr_term))(i)
+  alt((option, expression))(i)
 }
 
+/// Parenthesised arguments, shared by functions and annotations
+#[tracable_parser]
+fn function_args(i: Span) -> Result<Span, Vec<Node>> {
+  comma_list('(', function_arg, ')')(i)
+}
+
+/// `fun(a, b)`, `fun (a, b)` or `mod.fun(a, b)`. Keywords can't be called with
+/// a space before the arguments, so that e.g. `not (x)` stays an operation.
 #[tracable_parser]
 fn function(i: Span) -> Result {
   map(
-    tuple((
-      identifier,
-      opt(preceded(char('.'), identifier)),
-      delimited(
-        char('('),
-        opt(tuple((
-          preceded(multispace0, function_arg),
-          many0(preceded(
-            pair(
-              preceded(multispace0, char(',')),
-              preceded(multispace0, opt(line_ending)),
-            ),
-            function_arg,
-          )),
-        ))),
-        preceded(multispace0, char(')')),
-      ),
-    )),
-    |(name, subfunction, args)| {
+    verify(
+      tuple((
+        alt((since(2, handle), identifier)),
+        opt(preceded(char('.'), identifier)),
+        space0,
+        function_args,
+      )),
+      |(name, subfunction, space, _): &(Node, Option<Node>, Span, _)| {
+        let keyword = subfunction.is_none() && KEYWORDS.contains(&name.token.as_identifier().unwrap_or_default());
+        // A handle is only called through one of its methods, e.g. `@treasury.balance(eth)`
+        let handle = subfunction.is_none() && name.token.as_handle().is_some();
+        !handle && (space.fragment().is_empty() || (!keyword && grammar_version() >= 2))
+      },
+    ),
+    |(name, subfunction, _, args)| {
       let name = Rc::new(name);
       let subfunction = subfunction.map(Rc::new);
 
-      let mut f = Function {
+      let f = Function {
         name: Rc::clone(&name),
         subfunction,
-        args: Vec::new(),
+        args,
       };
 
-      if let Some((first, mut tail)) = args {
-        f.args.push(first);
-        f.args.append(&mut tail);
-      }
-
       Node::from_node(Token::Function(f), &name)
     },
   )(i)
@@ -152,12 +181,20 @@ fn sub_expression(i: Span) -> Result {
 
 #[tracable_parser]
 fn expr_term(i: Span) -> Result {
+  alt((since(2, trading_pair), unpaired_term))(i)
+}
+
+/// Any term but a trading pair, so that `cond ? a:b` is still a ternary
+#[tracable_parser]
+fn unpaired_term(i: Span) -> Result {
   let (rest, term) = alt((
     address,
     literal,
     collection,
     if_statement,
+    since(2, match_expr),
     function,
+    since(2, handle),
     identifier,
     sub_expression,
   ))(i)?;
@@ -182,37 +219,63 @@ fn expr_term(i: Span) -> Result {
   })(rest)
 }
 
+/// `if(cond, a, b)`, or `if(cond, a)` without an else branch
 #[tracable_parser]
 fn if_statement(i: Span) -> Result {
   map(
-    tuple((
-      tag_no_case("if"),
-      delimited(
-        char('('),
-        tuple((
-          preceded(multispace0, expression),
-          preceded(pair(char(','), multispace0), expression),
-          opt(preceded(pair(char(','), multispace0), expression)),
-        )),
-        preceded(multispace0, char(')')),
+    preceded(
+      pair(
+        tag_no_case("if"),
+        verify(space0, |space: &Span| {
+          space.fragment().is_empty() || grammar_version() >= 2
+        }),
       ),
-    )),
-    |(_, (cond, left, right))| {
-      let cond = Rc::new(cond);
-      let right = right.map(Rc::new);
+      verify(comma_list('(', expression, ')'), |args: &Vec<Node>| {
+        (2..=3).contains(&args.len())
+      }),
+    ),
+    |args| {
+      let mut args = args.into_iter().map(Rc::new);
+      let cond = args.next().unwrap();
       let c = Conditional {
         condition: Rc::clone(&cond),
-        if_true: Rc::new(left),
-        if_false: right,
+        if_true: args.next().unwrap(),
+        if_false: args.next(),
       };
       Node::from_node(Token::Conditional(c), &cond)
     },
   )(i)
 }
 
+/// Block form of `if`, e.g. `if cond { ... } else if cond { ... } else { ... }`.
+/// An `else if` is nested as the `if_false` branch.
+#[tracable_parser]
+fn if_block(i: Span) -> Result {
+  map(
+    tuple((
+      terminated(tag_no_case("if"), alt((space1, peek(tag("("))))),
+      expression,
+      multispace0,
+      body,
+      opt(preceded(
+        tuple((multispace0, tag_no_case("else"), multispace0)),
+        alt((if_block, body)),
+      )),
+    )),
+    |(keyword, cond, _, if_true, if_false)| {
+      let c = Conditional {
+        condition: Rc::new(cond),
+        if_true: Rc::new(if_true),
+        if_false: if_false.map(Rc::new),
+      };
+      Node::new(Token::Conditional(c), &keyword)
+    },
+  )(i)
+}
+
 #[tracable_parser]
 fn expression(i: Span) -> Result {
-  alt((operation, expr_term))(i)
+  operation_or_term(i)
 }
 
 #[tracable_parser]
@@ -227,6 +290,28 @@ fn attribute(i: Span) -> Result {
       let ident = Rc::new(ident);
       let attr = Attribute {
         ident: Rc::clone(&ident),
+        type_expr: None,
+        expr: Rc::new(value),
+      };
+      Node::from_node(Token::Attribute(attr), &ident)
+    },
+  )(i)
+}
+
+/// `x = 1` or `x: address = 0x...`, an attribute with an optional type annotation
+#[tracable_parser]
+fn binding(i: Span) -> Result {
+  map(
+    tuple((
+      identifier,
+      opt(type_annotation),
+      preceded(tuple((space0, char('='), space0)), expression),
+    )),
+    |(ident, type_expr, value)| {
+      let ident = Rc::new(ident);
+      let attr = Attribute {
+        ident: Rc::clone(&ident),
+        type_expr: type_expr.map(Rc::new),
         expr: Rc::new(value),
       };
       Node::from_node(Token::Attribute(attr), &ident)
@@ -249,40 +334,158 @@ fn option(i: Span) -> Result {
   )(i)
 }
 
-fn file(i: Span) -> OResult {
-  let (_, tree) = all_consuming(complete(fold_many1(
+/// Ends a statement: a `;`, a trailing comment, new lines, the end of input or
+/// the closing brace of a block
+#[tracable_parser]
+fn statement_end(i: Span) -> Result<Span, Span> {
+  preceded(
+    space0,
+    alt((
+      tag(";"),
+      recognize(tuple((opt(tag(";")), space0, line_comment, opt(line_ending)))),
+      eof,
+      recognize(many1(line_ending)),
+      peek(tag("}")),
+    )),
+  )(i)
+}
+
+#[tracable_parser]
+fn statement(i: Span) -> Result {
+  annotated(unannotated_statement)(i)
+}
+
+#[tracable_parser]
+fn unannotated_statement(i: Span) -> Result {
+  alt((
+    since(2, if_block),
+    since(2, when_block),
+    since(2, every_block),
+    since(2, atomic_block),
+    since(2, try_block),
+    since(2, for_loop),
+    since(2, definition),
+    since(2, return_statement),
+    since(2, assertion),
+    since(2, binding),
+    expression,
+  ))(i)
+}
+
+#[tracable_parser]
+fn statement_or_comment(i: Span) -> Result {
+  delimited(multispace0, alt((statement, line_comment)), statement_end)(i)
+}
+
+fn push_statement(mut tree: Tree, node: Node) -> Tree {
+  match node.token {
+    // Filter out line comments
+    // TODO: Better strip at parse time
+    // OR make all comments top-level and then strip
+    Token::LineComment(_) => {}
+    _ => tree.push(node),
+  }
+  tree
+}
+
+/// A block of statements in braces, separated the same way as in a file
+#[tracable_parser]
+fn body(i: Span) -> Result {
+  let (i, start) = position(i)?;
+  map(
     delimited(
-      multispace0,
-      alt((expression, line_comment)),
-      tuple((
-        space0,
-        alt((
-          tag(";"),
-          recognize(tuple((opt(tag(";")), space0, line_comment, opt(line_ending)))),
-          eof,
-          recognize(many1(line_ending)),
+      char('{'),
+      fold_many0(statement_or_comment, Tree::new(), push_statement),
+      preceded(multispace0, char('}')),
+    ),
+    move |statements| Node::new(Token::Body(statements), &start),
+  )(i)
+}
+
+/// Parameters are only allowed at the top of a file, before any statement
+#[tracable_parser]
+fn header_statement_or_comment(i: Span) -> Result {
+  delimited(
+    multispace0,
+    alt((
+      since(2, documented(param_declaration)),
+      since(2, documented(import_statement)),
+      // leave doc comments and directives to the statement they apply to
+      preceded(
+        not(since(
+          2,
+          alt((
+            tag("##"),
+            recognize(ignore_next_line_directive),
+            recognize(ignore_directive),
+          )),
         )),
-      )),
+        line_comment,
+      ),
+    )),
+    statement_end,
+  )(i)
+}
+
+/// Imports are only allowed at the top level of a file
+#[tracable_parser]
+fn top_level_statement_or_comment(i: Span) -> Result {
+  delimited(
+    multispace0,
+    alt((since(2, documented(import_statement)), statement, line_comment)),
+    statement_end,
+  )(i)
+}
+
+fn file(i: Span) -> OResult {
+  // forget errors left over from an earlier parse
+  take_token_error();
+  let (_, (mut tree, mut statements)) = all_consuming(complete(preceded(
+    not(eof),
+    pair(
+      fold_many0(header_statement_or_comment, Tree::new(), push_statement),
+      fold_many0(top_level_statement_or_comment, Tree::new(), push_statement),
     ),
-    Tree::new(),
-    |mut tree, node| {
-      match node.token {
-        // Filter out top-level line comments
-        // TODO: Better strip at parse time
-        // OR make all comments top-level and then strip
-        Token::LineComment(_) => {}
-        _ => tree.push(node),
-      }
-      tree
-    },
-  )))(i)?;
+  )))(i)
+  .map_err(|e| match take_token_error() {
+    Some(error) => error.into(),
+    None => Box::<dyn Error>::from(e),
+  })?;
 
+  tree.append(&mut statements);
   Ok(tree)
 }
 
-pub fn parse(i: &str) -> OResult {
+pub fn parse(i: &str) -> OResult<'_> {
+  Ok(parse_program(i)?.tree)
+}
+
+/// Parses a script along with its metadata. A version pragma on the first line
+/// selects the grammar the rest of the script is parsed with.
+pub fn parse_program(i: &str) -> SResult<Program, Box<dyn Error + '_>> {
   let span = Span::new_extra(i, get_tracer());
-  file(span)
+  let (span, metadata) = match pragma(span) {
+    Ok((rest, version)) => (
+      rest,
+      Metadata {
+        version: supported_version(&version)?,
+      },
+    ),
+    Err(Err::Failure((rest, _))) => {
+      let line = i.lines().nth(rest.location_line() as usize - 1).unwrap_or_default();
+      return Err(PragmaError::InvalidPragmaError(line.trim().to_string()).into());
+    }
+    Err(_) => (span, Metadata::default()),
+  };
+
+  let tree = with_version(metadata.version, || file(span))?;
+  Ok(Program { metadata, tree })
+}
+
+/// Parses with additional units (e.g. `usdc` with 6 decimals) that amounts are
+/// scaled into base units by
+pub fn parse_with_denominations(i: &str, denominations: Denominations) -> OResult<'_> {
+  with_denominations(denominations, || parse(i))
 }
 
 #[cfg(test)]
@@ -454,6 +657,69 @@ mod test {
         case(r#"fun(or_my_label)"#, function!("fun", none, ident!("or_my_label"))),
         case(r#"fun(in_my_label)"#, function!("fun", none, ident!("in_my_label"))),
         case(r#"fun(not(not_my_label))"#, function!("fun", none, function!("not", none, ident!("not_my_label")))),
+        case(
+          "swap(1.5 ETH, usdc, fee=30bps)",
+          function!("swap", none, amount!(1.5, "eth"), ident!("usdc"), opt!("fee", amount!(30, "bps")))
+        ),
+        case("fun(1 and 0)", function!("fun", none, binary_op!(number!(1), "&&", number!(0)))),
+        case(
+          "transfer(100 USDC, @treasury, from=@alice.hot)",
+          function!(
+            "transfer",
+            none,
+            amount!(100, "usdc"),
+            handle!("treasury"),
+            opt!("from", handle!("alice", "hot"))
+          )
+        ),
+        case(
+          "@treasury.balance(eth)",
+          Token::Function(Function {
+            name: node!(rc handle!("treasury")),
+            subfunction: some!(node!(rc ident!("balance"))),
+            args: vec![node!(ident!("eth"))],
+          })
+        ),
+        case(
+          "@team.ops.balance (eth)",
+          Token::Function(Function {
+            name: node!(rc handle!("team", "ops")),
+            subfunction: some!(node!(rc ident!("balance"))),
+            args: vec![node!(ident!("eth"))],
+          })
+        ),
+        case(
+          "price(ETH:USDC, ETH/USDC)",
+          function!(
+            "price",
+            none,
+            pair!(ident!("eth"), ident!("usdc")),
+            binary_op!(ident!("eth"), "/", ident!("usdc"))
+          )
+        ),
+        case(
+          "schedule(every=2h30m, until=2026-01-01T00:00:00Z, pool=1d_pool)",
+          function!(
+            "schedule",
+            none,
+            opt!("every", duration!(9_000_000)),
+            opt!("until", timestamp!("2026-01-01T00:00:00Z")),
+            opt!("pool", ident!("1d_pool"))
+          )
+        ),
+        case(
+          "fun(0xff, 0b1010, 0o755, 0x10 gwei, 0xcac725bef4f114f728cbcfd744a731c2a463c3fc, 0xcac725bef4f114f728cbcfd744a731c2a463c3fc_00)",
+          function!(
+            "fun",
+            none,
+            number!(255),
+            number!(10),
+            number!(493),
+            amount!(16, "gwei", 16_000_000_000),
+            address!("0xcac725bef4f114f728cbcfd744a731c2a463c3fc"),
+            number!("296360261666492356544587527351220535428824745376768".parse::<N>().unwrap())
+          )
+        ),
     )]
   fn test_function(input: &'static str, expected: Token, info: TracableInfo) -> Result {
     let input = Span::new_extra(input, info);
@@ -541,6 +807,50 @@ mod test {
 
     Ok(())
   }
+  #[rstest(input, expected,
+        case("if ready { a() }", conditional!(ident!("ready"), body!(function!("a")))),
+        case(
+          "IF x > 1 { a(); b() } ELSE { c() }",
+          conditional!(
+            binary_op!(ident!("x"), ">", number!(1)),
+            body!(function!("a"), function!("b")),
+            body!(function!("c"))
+          )
+        ),
+        case(
+          "if(x) {\n  a()\n} else if y {\n  b() # comment\n}\nelse {}",
+          conditional!(
+            ident!("x"),
+            body!(function!("a")),
+            conditional!(ident!("y"), body!(function!("b")), body!())
+          )
+        ),
+        case(
+          "if ready { if(ready, a(), b()) }",
+          conditional!(ident!("ready"), body!(conditional!(ident!("ready"), function!("a"), function!("b"))))
+        ),
+    )]
+  fn test_if_block(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let input = Span::new_extra(input, info);
+    let (span, node) = if_block(input)?;
+    assert!(span.fragment().is_empty());
+    node.assert_same_token(&node!(expected));
+
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("if ready"),
+    case("ifready { a() }"),
+    case("if ready { a() } else"),
+    case("if ready { a() } else b()"),
+    case("if(ready, a(), b())")
+  )]
+  fn test_if_block_invalid(input: &'static str, info: TracableInfo) {
+    let input = Span::new_extra(input, info);
+    assert!(all_consuming(if_block)(input).is_err());
+  }
 
   #[rstest(input, expected,
         case(
@@ -678,6 +988,224 @@ mod test {
             ),
           ]
       ),
+      case(
+          r#"# Rebalance on a schedule
+          every 1h {
+            rebalance() # trailing comment
+          }
+
+          when price(eth) > 2000 { sell(eth); notify() }
+          fun()"#,
+          vec![
+            node!(every!(duration!(3_600_000), body!(function!("rebalance")))),
+            node!(
+              when!(
+                binary_op!(function!("price", none, ident!("eth")), ">", number!(2000)),
+                body!(function!("sell", none, ident!("eth")), function!("notify"))
+              )
+            ),
+            node!(function!("fun")),
+          ]
+      ),
+      case(
+          r#"if balance(usdc) > 100 {
+            swap(usdc, eth)
+          } else {
+            notify("low balance")
+          }
+          if(true, a(), b())"#,
+          vec![
+            node!(
+              conditional!(
+                binary_op!(function!("balance", none, ident!("usdc")), ">", number!(100)),
+                body!(function!("swap", none, ident!("usdc"), ident!("eth"))),
+                body!(function!("notify", none, string!("low balance")))
+              )
+            ),
+            node!(conditional!(boolean!(true), function!("a"), function!("b"))),
+          ]
+      ),
+      case(
+          r#"atomic(chain=ethereum) {
+            approve(usdc, 100)
+            swap(usdc, eth)
+          }
+          atomic(1)"#,
+          vec![
+            node!(
+              atomic!(
+                [opt!("chain", ident!("ethereum"))],
+                body!(
+                  function!("approve", none, ident!("usdc"), number!(100)),
+                  function!("swap", none, ident!("usdc"), ident!("eth"))
+                )
+              )
+            ),
+            node!(function!("atomic", none, number!(1))),
+          ]
+      ),
+      case(
+          r#"REQUIRE(balance(usdc) >= 100, "insufficient USDC")
+          when ready { assert(x > 0) }"#,
+          vec![
+            node!(assertion!(
+              require,
+              binary_op!(function!("balance", none, ident!("usdc")), ">=", number!(100)),
+              string!("insufficient USDC")
+            )),
+            node!(when!(
+              ident!("ready"),
+              body!(assertion!(assert, binary_op!(ident!("x"), ">", number!(0))))
+            )),
+          ]
+      ),
+      case(
+          r#"try {
+            swap(usdc, eth)
+          } catch err {
+            notify(err)
+          }
+          swap(usdc, eth) else notify("swap failed")"#,
+          vec![
+            node!(try_catch!(
+              body!(function!("swap", none, ident!("usdc"), ident!("eth"))),
+              "err",
+              body!(function!("notify", none, ident!("err")))
+            )),
+            node!(binary_op!(
+              function!("swap", none, ident!("usdc"), ident!("eth")),
+              "else",
+              function!("notify", none, string!("swap failed"))
+            )),
+          ]
+      ),
+      case(
+          r#"for token in [eth, btc, sol] {
+            buy(token, 100 USDC)
+          }
+          1 in [1]"#,
+          vec![
+            node!(for_loop!(
+              "token",
+              list!(ident!("eth"), ident!("btc"), ident!("sol")),
+              body!(function!("buy", none, ident!("token"), amount!(100, "usdc")))
+            )),
+            node!(binary_op!(number!(1), "in", list!(number!(1)))),
+          ]
+      ),
+      case(
+          r#"def buy_dip(token, amount=100 USDC) {
+            if price(token) > 10 { return }
+            return buy(token, amount)
+          }
+          buy_dip(eth)"#,
+          vec![
+            node!(def!(
+              "buy_dip",
+              [ident!("token"), opt!("amount", amount!(100, "usdc"))],
+              body!(
+                conditional!(
+                  binary_op!(function!("price", none, ident!("token")), ">", number!(10)),
+                  body!(ret!())
+                ),
+                ret!(function!("buy", none, ident!("token"), ident!("amount")))
+              )
+            )),
+            node!(function!("buy_dip", none, ident!("eth"))),
+          ]
+      ),
+      case(
+          r#"import "lib/common.vxl"
+          import "x.vxl" as x
+          x.run()"#,
+          vec![
+            node!(import!("lib/common.vxl")),
+            node!(import!("x.vxl", "x")),
+            node!(function!("x", "run")),
+          ]
+      ),
+      case(
+          r#"@retry(3) @timeout(30s)
+          swap(usdc, eth)
+          @treasury.hot
+          @treasury.balance(eth)"#,
+          vec![
+            node!(function!("swap", none, ident!("usdc"), ident!("eth"))),
+            node!(handle!("treasury", "hot")),
+            node!(Token::Function(Function {
+              name: node!(rc handle!("treasury")),
+              subfunction: some!(node!(rc ident!("balance"))),
+              args: vec![node!(ident!("eth"))],
+            })),
+          ]
+      ),
+      case(
+          r#"param token: address
+          param amount: number = 100 USDC
+
+          buy(token, amount)"#,
+          vec![
+            node!(param!("token", type_expr!("address"), none)),
+            node!(param!("amount", type_expr!("number"), amount!(100, "usdc"))),
+            node!(function!("buy", none, ident!("token"), ident!("amount"))),
+          ]
+      ),
+      case(
+          r#"treasury: address = 0x0000000000000000000000000000000000000001
+          def pay(to: address, amounts: list<number>?, memo="") { send(to, amounts) }
+          eth:usdc"#,
+          vec![
+            node!(attr!(
+              "treasury",
+              type_expr!("address"),
+              address!("0x0000000000000000000000000000000000000001")
+            )),
+            node!(def!(
+              "pay",
+              [
+                param!("to", type_expr!("address"), none),
+                param!("amounts", type_expr!("list", [type_expr!("number")], optional), none),
+                opt!("memo", string!(""))
+              ],
+              body!(function!("send", none, ident!("to"), ident!("amounts")))
+            )),
+            node!(pair!(ident!("eth"), ident!("usdc"))),
+          ]
+      ),
+      case(
+          r#"limit = 5 ETH
+          limit == 5 ETH"#,
+          vec![
+            node!(attr!("limit", amount!(5, "eth"))),
+            node!(binary_op!(ident!("limit"), "==", amount!(5, "eth"))),
+          ]
+      ),
+      case(
+          r#"match price(eth) {
+            0..1800 => buy(eth)
+            _ => none
+          }
+          match(1)"#,
+          vec![
+            node!(match_expr!(
+              function!("price", none, ident!("eth")),
+              match_arm!([range!(number!(0), number!(1800))], none, function!("buy", none, ident!("eth"))),
+              match_arm!([wildcard!()], none, ident!("none"))
+            )),
+            node!(function!("match", none, number!(1))),
+          ]
+      ),
+      case(
+          r#"when price(eth) between 1800 and 2000 { buy(eth) }
+          x not between 1 and 2"#,
+          vec![
+            node!(when!(
+              between!(function!("price", none, ident!("eth")), number!(1800), number!(2000)),
+              body!(function!("buy", none, ident!("eth")))
+            )),
+            node!(between!(ident!("x"), number!(1), number!(2), negated)),
+          ]
+      ),
     )]
   fn test_file(input: &'static str, expected: Tree, info: TracableInfo) -> Result {
     let input = Span::new_extra(input, info);
@@ -693,9 +1221,137 @@ mod test {
     Ok(())
   }
 
-  #[rstest(input, case("fun() fun2()"))]
+  #[rstest(
+    input,
+    case("fun() fun2()"),
+    case("when ready { a() b() }"),
+    case("}"),
+    case("return 1"),
+    case("")
+  )]
   fn test_file_invalid(input: &'static str, info: TracableInfo) {
     let input = Span::new_extra(input, info);
     assert!(file(input).is_err());
   }
+
+  #[test]
+  fn test_parse_with_denominations() -> Result {
+    let mut denominations = Denominations::default();
+    denominations.register("usdc", 6);
+
+    let tree = parse_with_denominations("swap(250.5 USDC, 1 ether)", denominations.clone())?;
+    let expected = node!(function!(
+      "swap",
+      none,
+      amount!(250.5, "usdc", 250_500_000),
+      amount!(1, "ether", 1_000_000_000_000_000_000)
+    ));
+    tree[0].assert_same_token(&expected);
+
+    assert!(parse_with_denominations("swap(0.0000001 USDC)", Denominations::default()).is_ok());
+    assert!(parse_with_denominations("swap(0.0000001 USDC)", denominations).is_err());
+
+    Ok(())
+  }
+
+  /// The same items laid out in every way a comma list accepts
+  const LAYOUTS: [(&str, &str, &str, &str); 7] = [
+    ("", ",", "", ""),
+    ("", ", ", "", ","),
+    (" ", " , ", " ", ""),
+    (" ", ", ", "", ", "),
+    ("\n  ", ",\n  ", "\n", ""),
+    ("\n  ", ",\n  ", "\n", ","),
+    ("\n\n  ", "\n  ,\n\n  ", "\n", " ,"),
+  ];
+
+  fn lay_out(items: &[&str], (leading, separator, trailing, comma): (&str, &str, &str, &str)) -> String {
+    format!("{}{}{}{}", leading, items.join(separator), comma, trailing)
+  }
+
+  #[rstest(template, items, expected,
+    case("fun({})", &["1", "x"], function!("fun", none, number!(1), ident!("x"))),
+    case("fun ({})", &["1", "x"], function!("fun", none, number!(1), ident!("x"))),
+    case("a.b({})", &["slippage=1%"], function!("a", "b", opt!("slippage", percentage!(1)))),
+    case("[{}]", &["1", "[2]"], list![number!(1), list![number!(2)]]),
+    case("if({})", &["x", "a()", "b()"], conditional!(ident!("x"), function!("a"), function!("b"))),
+    case("if ({})", &["x", "a()"], conditional!(ident!("x"), function!("a"))),
+    case(
+      "require({})",
+      &["x > 0", "\"m\""],
+      assertion!(require, binary_op!(ident!("x"), ">", number!(0)), string!("m"))
+    ),
+    case("assert ({})", &["x"], assertion!(assert, ident!("x"))),
+    case(
+      "atomic({}) {{ a() }}",
+      &["chain=eth", "gas=1"],
+      atomic!([opt!("chain", ident!("eth")), opt!("gas", number!(1))], body!(function!("a")))
+    ),
+    case(
+      "def f({}) {{ a() }}",
+      &["x", "y=1"],
+      def!("f", [ident!("x"), opt!("y", number!(1))], body!(function!("a")))
+    ),
+  )]
+  fn test_comma_list_layouts(template: &str, items: &[&str], expected: Token) -> Result {
+    let expected = node!(expected);
+    for layout in LAYOUTS.iter() {
+      let input = template
+        .replace("{}", &lay_out(items, *layout))
+        .replace("{{", "{")
+        .replace("}}", "}");
+      let tree = parse(&input).map_err(|e| format!("{:?}: {}", input, e))?;
+      assert_eq!(tree.len(), 1, "{:?}", input);
+      tree[0].assert_same_token(&expected);
+    }
+
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("fun(,)"),
+    case("fun(1,,)"),
+    case("fun(,1)"),
+    case("fun(1 2)"),
+    case("if (x)"),
+    case("if (x, a, b, c)"),
+    case("atomic(,) { a() }"),
+    case("def f(,) { a() }"),
+    case("require(,)"),
+    case("assert(x,,)")
+  )]
+  fn test_comma_list_layouts_invalid(input: &'static str) {
+    assert!(parse(input).is_err());
+  }
+
+  #[rstest(input, expected,
+    case("not (x)", unary_op!("!", ident!("x"))),
+    case("a and (b)", binary_op!(ident!("a"), "&&", ident!("b"))),
+  )]
+  fn test_keyword_before_paren(input: &'static str, expected: Token) -> Result {
+    parse(input)?[0].assert_same_token(&node!(expected));
+    Ok(())
+  }
+
+  #[rstest(
+    open,
+    close,
+    case("(", ")"),
+    case("[", "]"),
+    case("fun(", ")"),
+    case("(1 + ", ")"),
+    case("(x between ", " and y)")
+  )]
+  fn test_deeply_nested(open: &str, close: &str) {
+    let depth = 40;
+    let input = format!("{}x{}", open.repeat(depth), close.repeat(depth));
+    let start = std::time::Instant::now();
+    assert!(parse(&input).is_ok());
+    assert!(
+      start.elapsed() < std::time::Duration::from_secs(1),
+      "took {:?}",
+      start.elapsed()
+    );
+  }
 }
@@ -1,26 +1,48 @@
 use nom::{
   character::complete::{char, multispace0},
   combinator::{map, not, opt, recognize},
-  multi::many0,
-  sequence::{pair, preceded, terminated, tuple},
+  multi::{many0, separated_list1},
+  sequence::{delimited, pair, preceded, terminated, tuple},
 };
-
+use nom_locate::position;
 use nom_tracable::tracable_parser;
 
-use crate::{expression, Node, Result, Span, Token};
+use crate::{expression, grammar_version, since, Node, Result, Span, Token};
+
+/// Comma separated `item`s between `open` and `close`, e.g. `(a, b)`. Items may
+/// be spread over several lines and, since version 2, be followed by a trailing
+/// comma, so that calls, lists and option lists all share one layout.
+pub(crate) fn comma_list<'a>(
+  open: char,
+  item: impl FnMut(Span<'a>) -> Result<'a>,
+  close: char,
+) -> impl FnMut(Span<'a>) -> Result<'a, Span<'a>, Vec<Node>> {
+  map(
+    delimited(
+      pair(char(open), multispace0),
+      opt(terminated(
+        separated_list1(delimited(multispace0, char(','), multispace0), item),
+        opt(since(2, pair(multispace0, char(',')))),
+      )),
+      pair(multispace0, char(close)),
+    ),
+    Option::unwrap_or_default,
+  )
+}
 
 #[tracable_parser]
-fn list_end(i: Span) -> Result {
+fn list_end_v1(i: Span) -> Result {
   map(tuple((opt(char(',')), multispace0, char(']'))), |(_, _, _)| {
     Node::default()
   })(i)
 }
 
+/// Lists as parsed before version 2, where e.g. `[,]` is an empty list
 #[tracable_parser]
-pub fn list(i: Span) -> Result {
+fn list_v1(i: Span) -> Result {
   let (i, start) = recognize(pair(char('['), multispace0))(i)?;
   // short-circuit empty list
-  if let Ok((i, _)) = list_end(i) {
+  if let Ok((i, _)) = list_end_v1(i) {
     return Ok((i, Node::new(Token::List(Vec::new()), &start)));
   }
 
@@ -28,9 +50,12 @@ pub fn list(i: Span) -> Result {
     terminated(
       tuple((
         opt(expression),
-        many0(pair(not(list_end), preceded(pair(char(','), multispace0), expression))),
+        many0(pair(
+          not(list_end_v1),
+          preceded(pair(char(','), multispace0), expression),
+        )),
       )),
-      list_end,
+      list_end_v1,
     ),
     move |(first, tail): (Option<Node>, Vec<((), Node)>)| {
       let items = first.map_or(Vec::new(), |head| {
@@ -44,6 +69,18 @@ pub fn list(i: Span) -> Result {
   )(i)
 }
 
+#[tracable_parser]
+pub fn list(i: Span) -> Result {
+  if grammar_version() < 2 {
+    return list_v1(i);
+  }
+
+  let (i, start) = position(i)?;
+  map(comma_list('[', expression, ']'), move |items| {
+    Node::new(Token::List(items), &start)
+  })(i)
+}
+
 #[cfg(test)]
 mod test {
   use crate::*;
@@ -112,4 +149,10 @@ mod test {
 
     Ok(())
   }
+
+  #[rstest(input, case("[,]"), case("[1,,]"), case("[1 2]"), case("[1,"), case("[, 1]"))]
+  fn test_list_invalid(input: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(nom::combinator::all_consuming(list)(span).is_err());
+  }
 }
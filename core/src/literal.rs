@@ -1,7 +1,7 @@
-use nom::{character::complete::anychar, combinator::peek, error::ErrorKind, Err};
+use nom::{branch::alt, character::complete::anychar, combinator::peek, error::ErrorKind, Err};
 use nom_tracable::tracable_parser;
 
-use crate::{boolean, numeric, string, Result, Span};
+use crate::{boolean, duration, numeric, since, string, timestamp, Result, Span};
 
 #[tracable_parser]
 pub fn literal(i: Span) -> Result {
@@ -9,7 +9,8 @@ pub fn literal(i: Span) -> Result {
   match head {
     't' | 'T' | 'f' | 'F' => boolean(i),
     '"' => string(i),
-    '-' | '0'..='9' => numeric(i),
+    '0'..='9' => alt((since(2, timestamp), since(2, duration), numeric))(i),
+    '-' => numeric(i),
     _ => Err(Err::Error((i, ErrorKind::Char))),
   }
 }
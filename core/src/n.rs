@@ -1,22 +1,32 @@
-use std::str::FromStr;
+use std::{convert::TryFrom, fmt, str::FromStr};
 
 use nom::{
-  bytes::complete::{tag_no_case, take_while1},
-  character::complete::{alpha1, char},
-  combinator::{map, not, opt},
+  branch::alt,
+  bytes::complete::{tag_no_case, take_while, take_while1},
+  character::complete::{alpha1, char, satisfy},
+  combinator::{map, not, opt, recognize},
   error::ErrorKind,
   sequence::{pair, preceded, terminated, tuple},
   Err,
 };
 use nom_tracable::tracable_parser;
+use num_bigint::BigInt;
 use rust_decimal::prelude::*;
 use serde::{Serialize, Serializer};
 
-use crate::{sign, Node, Operator, Result, Span, Token, TokenError};
+use crate::{
+  grammar_version, sign, since, token_failure, valid_ident_char_a, Node, Operator, Result, Span, Token, TokenError,
+  ATTACHED_UNITS,
+};
+
+/// Integers of up to 256 bits in magnitude cover both U256 and I256 amounts
+const MAX_INT_BITS: u64 = 256;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum N {
   Int(i64),
+  /// Integers that don't fit in an `i64`, such as raw token amounts
+  BigInt(BigInt),
   Decimal(Decimal),
 }
 
@@ -40,11 +50,22 @@ impl From<Decimal> for N {
   }
 }
 
+/// Integers are only kept as `BigInt` when they don't fit in an `i64`
+impl From<BigInt> for N {
+  fn from(b: BigInt) -> Self {
+    b.to_i64().map_or(N::BigInt(b), N::Int)
+  }
+}
+
 impl N {
   pub fn is_int(&self) -> bool {
     self.as_int().is_some()
   }
 
+  pub fn is_big_int(&self) -> bool {
+    self.as_big_int().is_some()
+  }
+
   pub fn is_decimal(&self) -> bool {
     self.as_decimal().is_some()
   }
@@ -57,6 +78,14 @@ impl N {
     }
   }
 
+  pub fn as_big_int(&self) -> Option<&BigInt> {
+    if let N::BigInt(b) = self {
+      Some(b)
+    } else {
+      None
+    }
+  }
+
   pub fn as_decimal(&self) -> Option<Decimal> {
     if let N::Decimal(f) = self {
       Some(*f)
@@ -67,10 +96,102 @@ impl N {
 
   pub fn negate(&self) -> Self {
     match self {
-      N::Int(i) => N::Int(i * -1),
+      N::Int(i) => i.checked_neg().map_or_else(|| (-BigInt::from(*i)).into(), N::Int),
+      N::BigInt(b) => N::from(-b),
       N::Decimal(d) => N::Decimal(d * Decimal::NEGATIVE_ONE),
     }
   }
+
+  /// Exactly scales the number by `10^decimals` into an integer, e.g. `1.5` ether
+  /// with 18 decimals into wei. Errors rather than rounding if a fraction remains.
+  pub fn scale(&self, decimals: u32) -> std::result::Result<Self, TokenError> {
+    let scaled = self.shift(decimals.into())?;
+    if scaled.is_decimal() {
+      return Err(TokenError::FractionalBaseUnitError(self.to_string()));
+    }
+
+    Ok(scaled)
+  }
+
+  /// Multiplies the number by `10^exp` without losing precision. Whole results
+  /// are integers, anything else is a decimal.
+  fn shift(&self, exp: i64) -> std::result::Result<Self, TokenError> {
+    let overflow = || TokenError::ScaleOverflowError(self.to_string());
+
+    // the number is `mantissa * 10^-scale`
+    let (mantissa, scale) = match self {
+      N::Int(i) => (BigInt::from(*i), 0),
+      N::BigInt(b) => (b.clone(), 0),
+      N::Decimal(d) => {
+        let d = d.normalize();
+        (BigInt::from(d.mantissa()), i64::from(d.scale()))
+      }
+    };
+    let scale = scale.checked_sub(exp).ok_or_else(overflow)?;
+
+    if scale > 0 {
+      let mantissa = mantissa.to_i128().ok_or_else(overflow)?;
+      let scale = u32::try_from(scale).map_err(|_| overflow())?;
+      return Decimal::try_from_i128_with_scale(mantissa, scale)
+        .map(N::Decimal)
+        .map_err(|_| overflow());
+    }
+
+    let pow = u32::try_from(-scale).map_err(|_| overflow())?;
+    if mantissa.is_zero() {
+      return Ok(N::Int(0));
+    }
+    // 10^78 alone is already more than 256 bits
+    if pow > 77 {
+      return Err(overflow());
+    }
+
+    let shifted = mantissa * BigInt::from(10).pow(pow);
+    if shifted.bits() > MAX_INT_BITS {
+      return Err(overflow());
+    }
+
+    Ok(shifted.into())
+  }
+
+  /// Numbers as parsed before version 2: integers that don't fit in an `i64`
+  /// are decimals
+  fn from_str_v1(s: &str) -> std::result::Result<Self, TokenError> {
+    match s.parse::<i64>() {
+      Ok(i) => Ok(N::Int(i)),
+      Err(_) => Ok(N::Decimal(Decimal::from_str(s)?)),
+    }
+  }
+
+  /// Exponents as applied before version 2: a positive exponent always gives an
+  /// integer, truncating any fraction, and a negative one always a decimal
+  fn shift_v1(&self, exp: i64) -> std::result::Result<Self, TokenError> {
+    let overflow = || TokenError::ScaleOverflowError(self.to_string());
+    let pow = u32::try_from(exp.unsigned_abs())
+      .ok()
+      .and_then(|exp| 10i64.checked_pow(exp))
+      .ok_or_else(overflow)?;
+    let inverse = Decimal::ONE / Decimal::from(pow);
+
+    let shifted = match self {
+      N::Int(i) if exp < 0 => Decimal::from(*i).checked_mul(inverse).map(N::Decimal),
+      N::Int(i) => i.checked_mul(pow).map(N::Int),
+      N::Decimal(d) if exp < 0 => d.checked_mul(inverse).map(N::Decimal),
+      N::Decimal(d) => d.checked_mul(Decimal::from(pow)).and_then(|d| d.to_i64()).map(N::Int),
+      N::BigInt(_) => None,
+    };
+    shifted.ok_or_else(overflow)
+  }
+}
+
+impl fmt::Display for N {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      N::Int(i) => write!(f, "{}", i),
+      N::BigInt(b) => write!(f, "{}", b),
+      N::Decimal(d) => write!(f, "{}", d),
+    }
+  }
 }
 
 impl FromStr for N {
@@ -82,6 +203,15 @@ impl FromStr for N {
       return Ok(N::Int(i?));
     }
 
+    // promote integers that don't fit in an i64
+    if let Ok(b) = BigInt::from_str(s) {
+      if b.bits() > MAX_INT_BITS {
+        return Err(TokenError::IntRangeError(s.to_string()));
+      }
+
+      return Ok(N::BigInt(b));
+    }
+
     let d = Decimal::from_str(s);
     if d.is_ok() {
       return Ok(N::Decimal(d?));
@@ -97,15 +227,16 @@ impl Serialize for N {
   where
     S: Serializer,
   {
-    match *self {
-      N::Int(ref v) => serializer.serialize_newtype_variant("number", 0, "int", &v.to_string()),
-      N::Decimal(ref v) => serializer.serialize_newtype_variant("number", 1, "decimal", &v.to_string()),
+    match self {
+      N::Int(v) => serializer.serialize_newtype_variant("number", 0, "int", &v.to_string()),
+      N::BigInt(v) => serializer.serialize_newtype_variant("number", 0, "int", &v.to_string()),
+      N::Decimal(v) => serializer.serialize_newtype_variant("number", 1, "decimal", &v.to_string()),
     }
   }
 }
 
 fn is_digit_or_underscore(c: char) -> bool {
-  c.is_digit(10) || c == '_'
+  c.is_ascii_digit() || c == '_'
 }
 
 #[tracable_parser]
@@ -127,10 +258,73 @@ pub fn exponent(i: Span) -> Result<Span, i64> {
   }
 }
 
+/// Letters directly after the digits of a number make it an identifier (e.g. `1foo_v1`),
+/// unless they are an exponent or, since version 2, one of the attached units such as `30bps`
+#[tracable_parser]
+fn ident_suffix(i: Span) -> Result<Span, Span> {
+  let (rest, suffix) = recognize(preceded(opt(tag_no_case("e")), alpha1))(i)?;
+  let (_, tail) = take_while(valid_ident_char_a)(rest)?;
+
+  let unit = suffix.fragment().to_lowercase();
+  if tail.fragment().is_empty() && ATTACHED_UNITS.contains(&unit.as_str()) && grammar_version() >= 2 {
+    return Err(Err::Error((i, ErrorKind::Alpha)));
+  }
+
+  Ok((rest, suffix))
+}
+
+fn is_negative(maybe_sign: &Option<Node>) -> bool {
+  matches!(
+    maybe_sign,
+    Some(Node {
+      token: Token::Operator(Operator::Minus),
+      ..
+    })
+  )
+}
+
+/// Integers prefixed with their radix: `0xff`, `0b1010` or `0o755`. Note that a
+/// `0x` followed by exactly 40 hex digits is matched as an address before numbers
+/// are tried, see `address`.
+#[tracable_parser]
+fn radix_int(i: Span) -> Result<Span, N> {
+  let (rest, prefix) = alt((tag_no_case("0x"), tag_no_case("0b"), tag_no_case("0o")))(i)?;
+  let radix = match prefix.fragment().to_lowercase().as_str() {
+    "0x" => 16,
+    "0b" => 2,
+    _ => 8,
+  };
+
+  let (rest, digits) = terminated(
+    recognize(pair(
+      satisfy(|c| c.is_digit(radix)),
+      take_while(|c: char| c.is_digit(radix) || c == '_'),
+    )),
+    // e.g. `0b102` or `0xfoo` are identifiers
+    not(satisfy(valid_ident_char_a)),
+  )(rest)?;
+
+  let b = BigInt::parse_bytes(digits.fragment().replace('_', "").as_bytes(), radix)
+    .filter(|b| b.bits() <= MAX_INT_BITS)
+    .ok_or_else(|| token_failure(i, TokenError::IntRangeError(format!("{}{}", prefix, digits))))?;
+
+  Ok((rest, b.into()))
+}
+
 #[tracable_parser]
 pub fn n(i: Span) -> Result<Span, N> {
+  let start = i;
   let (i, maybe_sign) = opt(sign)(i)?;
 
+  match since(2, radix_int)(i) {
+    Ok((rest, num)) => {
+      let num = if is_negative(&maybe_sign) { num.negate() } else { num };
+      return Ok((rest, num));
+    }
+    Err(Err::Error(_)) => {}
+    Err(e) => return Err(e),
+  }
+
   let (i, num) = map(
     terminated(
       tuple((
@@ -138,7 +332,7 @@ pub fn n(i: Span) -> Result<Span, N> {
         opt(preceded(char('.'), take_while1(is_digit_or_underscore))),
       )),
       // allows identifiers starting with numbers
-      not(preceded(opt(tag_no_case("e")), alpha1)),
+      not(ident_suffix),
     ),
     |(dec, maybe_fract): (Span, Option<Span>)| {
       let mut buf = dec.fragment().replace('_', "");
@@ -147,8 +341,13 @@ pub fn n(i: Span) -> Result<Span, N> {
         buf.push_str(&fract.fragment().replace('_', ""));
       }
 
-      let n: N = buf.parse().map_err(|_| Err::Failure((dec, ErrorKind::Float)))?;
-      let n = if maybe_sign.is_some() { n.negate() } else { n };
+      let n = if grammar_version() >= 2 {
+        buf.parse()
+      } else {
+        N::from_str_v1(&buf)
+      };
+      let n = n.map_err(|e| token_failure(dec, e))?;
+      let n = if is_negative(&maybe_sign) { n.negate() } else { n };
 
       Ok(n)
     },
@@ -156,28 +355,26 @@ pub fn n(i: Span) -> Result<Span, N> {
 
   let num = num?;
 
-  let (i, maybe_exp) = opt(exponent)(i)?;
-  let num = maybe_exp.map_or(num, |exp| match num {
-    N::Int(i) => {
-      let pow = 10i64.pow(exp.unsigned_abs() as u32);
-      if exp < 0 {
-        let v = Decimal::from_i64(i).unwrap() * (Decimal::ONE / Decimal::from_i64(pow).unwrap());
-        N::Decimal(v)
-      } else {
-        N::Int(i * pow)
-      }
+  let (rest, maybe_exp) = opt(exponent)(i)?;
+  let text = || start.fragment()[..rest.location_offset() - start.location_offset()].to_string();
+  let num = match maybe_exp {
+    Some(exp) => if grammar_version() >= 2 {
+      num.shift(exp)
+    } else {
+      num.shift_v1(exp)
     }
-    N::Decimal(d) => {
-      let pow: Decimal = 10i64.pow(exp.unsigned_abs() as u32).into();
-      if exp < 0 {
-        N::Decimal(d * (Decimal::ONE / pow))
+    .map_err(|_| {
+      let error = if exp > 0 {
+        TokenError::IntRangeError(text())
       } else {
-        N::Int((d * pow).to_i64().unwrap())
-      }
-    }
-  });
-
-  Ok((i, num))
+        TokenError::DecimalRangeError(text())
+      };
+      token_failure(start, error)
+    })?,
+    None => num,
+  };
+
+  Ok((rest, num))
 }
 
 #[cfg(test)]
@@ -189,6 +386,13 @@ mod test {
   use rstest::rstest;
   use rust_decimal_macros::dec;
   use serde_test::{assert_ser_tokens, Token as SerdeToken};
+  use std::str::FromStr;
+
+  const U256_MAX: &str = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+
+  fn big(s: &str) -> N {
+    N::BigInt(num_bigint::BigInt::from_str(s).unwrap())
+  }
 
   #[rstest(input, expected,
       case("1.23", N::Decimal(dec!(1.23))),
@@ -208,6 +412,29 @@ mod test {
       case("-1_000e-4", N::Decimal(dec!(-1_000e-4))),
       case("-1e0_1", N::Int(-10)),
       case("0.333333333333333334", N::Decimal(dec!(0.333333333333333334))),
+      case("9223372036854775807", N::Int(i64::MAX)),
+      case("-9223372036854775808", N::Int(i64::MIN)),
+      case("9223372036854775808", big("9223372036854775808")),
+      case("-9223372036854775809", big("-9223372036854775809")),
+      case("1_000_000_000_000_000_000_000", big("1000000000000000000000")),
+      case(U256_MAX, big(U256_MAX)),
+      case("1e30", big("1000000000000000000000000000000")),
+      case("-2.5E20", big("-250000000000000000000")),
+      case("1e77", big(&format!("1{}", "0".repeat(77)))),
+      case("1.5e-27", N::Decimal(dec!(0.0000000000000000000000000015))),
+      case("1.2345e2", N::Decimal(dec!(123.45))),
+      case("0e100", N::Int(0)),
+      case("+5", N::Int(5)),
+      case("0xff", N::Int(255)),
+      case("0XFF", N::Int(255)),
+      case("-0x10", N::Int(-16)),
+      case("0xdead_beef", N::Int(0xdead_beef)),
+      case("0b1010", N::Int(10)),
+      case("0b1111_0000", N::Int(240)),
+      case("0o755", N::Int(493)),
+      case("0O1_7", N::Int(15)),
+      case("0x00", N::Int(0)),
+      case("0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff", big(U256_MAX)),
     )]
   fn test_n(input: &'static str, expected: N, info: TracableInfo) -> Result {
     let span = Span::new_extra(input, info);
@@ -218,6 +445,83 @@ mod test {
     Ok(())
   }
 
+  #[rstest(
+    input,
+    case("115792089237316195423570985008687907853269984665640564039457584007913129639936"),
+    case("1e78"),
+    case("1e9223372036854775807"),
+    case("1e-29"),
+    case("1.5e-28"),
+    case("0x1ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff")
+  )]
+  fn test_n_out_of_range(input: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(matches!(n(span), Err(nom::Err::Failure(_))));
+  }
+
+  #[rstest(
+    input,
+    expected,
+    case(
+      "fun(-115_792_089_237_316_195_423_570_985_008_687_907_853_269_984_665_640_564_039_457_584_007_913_129_639_936)",
+      "115792089237316195423570985008687907853269984665640564039457584007913129639936 is out of the 256-bit integer range"
+    ),
+    case("fun(1e78)", "1e78 is out of the 256-bit integer range"),
+    case("fun(-1.5e-28)", "-1.5e-28 has too many decimal places"),
+    case(
+      "fun(0x1ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff)",
+      "0x1ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff is out of the 256-bit integer range"
+    ),
+  )]
+  fn test_n_out_of_range_message(input: &'static str, expected: &str) {
+    let error = parse(input).unwrap_err();
+    assert_eq!(error.to_string(), expected);
+  }
+
+  #[rstest(
+    input,
+    case("0x"),
+    case("0x_1"),
+    case("0xfoo"),
+    case("0b102"),
+    case("0o8"),
+    case("0b")
+  )]
+  fn test_n_radix_invalid(input: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(matches!(n(span), Err(nom::Err::Error(_))));
+  }
+
+  #[rstest(input, decimals, expected,
+      case(N::Int(1), 18, N::Int(1_000_000_000_000_000_000)),
+      case(N::Int(20), 9, N::Int(20_000_000_000)),
+      case(N::Int(-3), 0, N::Int(-3)),
+      case(N::Decimal(dec!(1.5)), 18, N::Int(1_500_000_000_000_000_000)),
+      case(N::Decimal(dec!(0.000000001)), 9, N::Int(1)),
+      case(N::Decimal(dec!(-2.50)), 1, N::Int(-25)),
+      case(N::Decimal(dec!(7.000)), 0, N::Int(7)),
+      case(N::Int(10), 18, big("10000000000000000000")),
+      case(N::Decimal(dec!(9.3)), 18, big("9300000000000000000")),
+      case(N::Int(-1), 30, big("-1000000000000000000000000000000")),
+      case(big("10000000000000000000"), 0, big("10000000000000000000")),
+    )]
+  fn test_scale(input: N, decimals: u32, expected: N) -> Result {
+    assert_eq!(input.scale(decimals)?, expected);
+
+    Ok(())
+  }
+
+  #[rstest(input, decimals,
+      case(N::Decimal(dec!(1.5)), 0),
+      case(N::Decimal(dec!(0.0000000001)), 9),
+      case(N::Int(1), 78),
+      case(N::Int(1), u32::MAX),
+      case(big(U256_MAX), 1),
+    )]
+  fn test_scale_invalid(input: N, decimals: u32) {
+    assert!(input.scale(decimals).is_err());
+  }
+
   #[rstest(input, expected,
         case(N::Int(47), ("int", "47")),
         case(N::Int(17892037), ("int", "17892037")),
@@ -227,6 +531,8 @@ mod test {
         case(N::Int(1_0), ("int", "10")),
         case(N::Int(-1_0), ("int", "-10")),
         case(N::Int(-1_700_000_000_00), ("int", "-170000000000")),
+        case(big("10000000000000000000"), ("int", "10000000000000000000")),
+        case(big("-10000000000000000000"), ("int", "-10000000000000000000")),
         case(N::Decimal(dec!(1)), ("decimal", "1")),
         case(N::Decimal(dec!(1.0)), ("decimal", "1.0")),
         case(N::Decimal(dec!(1.00)), ("decimal", "1.00")),
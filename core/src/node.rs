@@ -1,7 +1,7 @@
 use serde::Serialize;
-use std::fmt::Debug;
+use std::{cell::RefCell, fmt::Debug, rc::Rc};
 
-use crate::{Span, Token};
+use crate::{Ignore, Setting, Span, Token};
 
 pub type Tree = Vec<Node>;
 
@@ -11,6 +11,18 @@ pub struct Node {
   pub offset: usize,
   pub line: u32,
   pub column: u32,
+  /// The file the node was parsed from, when parsed through `parse_file`
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub source: Option<Rc<str>>,
+  /// Annotations such as `@retry(3)` preceding a statement
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub annotations: Vec<Node>,
+  /// `##` doc comments preceding a statement, definition or parameter
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub docs: Option<String>,
+  /// Lint rules to ignore for a statement, from `# vxl-ignore` directives
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ignore: Option<Ignore>,
   pub token: Token,
 }
 
@@ -20,6 +32,10 @@ impl Default for Node {
       offset: 0,
       line: 0,
       column: 0,
+      source: None,
+      annotations: Vec::new(),
+      docs: None,
+      ignore: None,
       token: Token::Unknown,
     }
   }
@@ -32,6 +48,10 @@ impl Node {
       offset: span.location_offset(),
       line: span.location_line(),
       column: span.get_utf8_column() as u32,
+      source: SOURCE.with(|s| s.borrow().clone()),
+      annotations: Vec::new(),
+      docs: None,
+      ignore: None,
     }
   }
 
@@ -41,10 +61,24 @@ impl Node {
       offset: node.offset,
       line: node.line,
       column: node.column,
+      source: node.source.clone(),
+      annotations: Vec::new(),
+      docs: None,
+      ignore: None,
     }
   }
 }
 
+thread_local! {
+  static SOURCE: RefCell<Option<Rc<str>>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with `source` recorded as the file of every node parsed
+pub fn with_source<T>(source: &str, f: impl FnOnce() -> T) -> T {
+  let _source = Setting::set(&SOURCE, Some(Rc::from(source)));
+  f()
+}
+
 #[cfg(test)]
 mod test {
   use crate::N;
@@ -95,6 +129,16 @@ mod test {
             panic!("expected UnaryOp, got {:#?}; self is {:#?}", other.token, self.token);
           }
         }
+        Token::Between(token) => {
+          if let Some(between) = other.token.as_between() {
+            token.value.assert_same_token(&between.value);
+            token.low.assert_same_token(&between.low);
+            token.high.assert_same_token(&between.high);
+            assert_eq!(token.negated, between.negated);
+          } else {
+            panic!("expected Between, got {:#?}; self is {:#?}", other.token, self.token);
+          }
+        }
         Token::Number(N::Decimal(f1)) => {
           if let Some(N::Decimal(f2)) = other.token.as_number() {
             assert!((f1 - f2).abs() < Decimal::MAX);
@@ -129,6 +173,208 @@ mod test {
             );
           }
         }
+        Token::Pair(token) => {
+          if let Some(pair) = other.token.as_pair() {
+            token.base.assert_same_token(&pair.base);
+            token.quote.assert_same_token(&pair.quote);
+          } else {
+            panic!("expected Pair, got {:#?}; self is {:#?}", other.token, self.token);
+          }
+        }
+        Token::Body(statements) => {
+          let other_statements = other
+            .token
+            .as_body()
+            .unwrap_or_else(|| panic!("expected Body, got {:#?}; self is {:#?}", other.token, self.token));
+          assert_eq!(statements.len(), other_statements.len());
+          for (i, statement) in statements.iter().enumerate() {
+            statement.assert_same_token(&other_statements[i]);
+          }
+        }
+        Token::When(token) => {
+          if let Some(when) = other.token.as_when() {
+            token.condition.assert_same_token(&when.condition);
+            token.body.assert_same_token(&when.body);
+          } else {
+            panic!("expected When, got {:#?}; self is {:#?}", other.token, self.token);
+          }
+        }
+        Token::Every(token) => {
+          if let Some(every) = other.token.as_every() {
+            token.interval.assert_same_token(&every.interval);
+            token.body.assert_same_token(&every.body);
+          } else {
+            panic!("expected Every, got {:#?}; self is {:#?}", other.token, self.token);
+          }
+        }
+        Token::Atomic(token) => {
+          if let Some(atomic) = other.token.as_atomic() {
+            assert_eq!(token.options.len(), atomic.options.len());
+            for (i, option) in token.options.iter().enumerate() {
+              option.assert_same_token(&atomic.options[i]);
+            }
+            token.body.assert_same_token(&atomic.body);
+          } else {
+            panic!("expected Atomic, got {:#?}; self is {:#?}", other.token, self.token);
+          }
+        }
+        Token::Assert(token) | Token::Require(token) => {
+          let assertion = match (&self.token, &other.token) {
+            (Token::Assert(_), Token::Assert(a)) | (Token::Require(_), Token::Require(a)) => a,
+            _ => panic!("expected {:#?}, got {:#?}", self.token, other.token),
+          };
+          token.condition.assert_same_token(&assertion.condition);
+          if let Some(message) = &token.message {
+            message.assert_same_token_if_some(&assertion.message);
+          } else {
+            assert!(assertion.message.is_none())
+          }
+        }
+        Token::Try(token) => {
+          if let Some(t) = other.token.as_try() {
+            token.body.assert_same_token(&t.body);
+            if let Some(error) = &token.error {
+              error.assert_same_token_if_some(&t.error);
+            } else {
+              assert!(t.error.is_none())
+            }
+            token.catch.assert_same_token(&t.catch);
+          } else {
+            panic!("expected Try, got {:#?}; self is {:#?}", other.token, self.token);
+          }
+        }
+        Token::For(token) => {
+          if let Some(f) = other.token.as_for() {
+            token.binding.assert_same_token(&f.binding);
+            token.iterable.assert_same_token(&f.iterable);
+            token.body.assert_same_token(&f.body);
+          } else {
+            panic!("expected For, got {:#?}; self is {:#?}", other.token, self.token);
+          }
+        }
+        Token::Def(token) => {
+          if let Some(def) = other.token.as_def() {
+            token.name.assert_same_token(&def.name);
+            assert_eq!(token.params.len(), def.params.len());
+            for (i, param) in token.params.iter().enumerate() {
+              param.assert_same_token(&def.params[i]);
+            }
+            token.body.assert_same_token(&def.body);
+          } else {
+            panic!("expected Def, got {:#?}; self is {:#?}", other.token, self.token);
+          }
+        }
+        Token::Return(value) => {
+          let other_value = other
+            .token
+            .as_return()
+            .unwrap_or_else(|| panic!("expected Return, got {:#?}; self is {:#?}", other.token, self.token));
+          if let Some(value) = value {
+            value.assert_same_token_if_some(other_value);
+          } else {
+            assert!(other_value.is_none())
+          }
+        }
+        Token::Import(token) => {
+          if let Some(import) = other.token.as_import() {
+            assert_eq!(token.path, import.path);
+            if let Some(alias) = &token.alias {
+              alias.assert_same_token_if_some(&import.alias);
+            } else {
+              assert!(import.alias.is_none())
+            }
+          } else {
+            panic!("expected Import, got {:#?}; self is {:#?}", other.token, self.token);
+          }
+        }
+        Token::Annotation(token) => {
+          if let Some(annotation) = other.token.as_annotation() {
+            token.name.assert_same_token(&annotation.name);
+            assert_eq!(token.args.len(), annotation.args.len());
+            for (i, arg) in token.args.iter().enumerate() {
+              arg.assert_same_token(&annotation.args[i]);
+            }
+          } else {
+            panic!("expected Annotation, got {:#?}; self is {:#?}", other.token, self.token);
+          }
+        }
+        Token::Param(token) => {
+          if let Some(param) = other.token.as_param() {
+            token.name.assert_same_token(&param.name);
+            if let Some(type_expr) = &token.type_expr {
+              type_expr.assert_same_token_if_some(&param.type_expr);
+            } else {
+              assert!(param.type_expr.is_none())
+            }
+            if let Some(default) = &token.default {
+              default.assert_same_token_if_some(&param.default);
+            } else {
+              assert!(param.default.is_none())
+            }
+          } else {
+            panic!("expected Param, got {:#?}; self is {:#?}", other.token, self.token);
+          }
+        }
+        Token::Type(token) => {
+          if let Some(t) = other.token.as_type_expr() {
+            assert_eq!((&token.name, token.optional), (&t.name, t.optional));
+            assert_eq!(token.args.len(), t.args.len());
+            for (i, arg) in token.args.iter().enumerate() {
+              arg.assert_same_token(&t.args[i]);
+            }
+          } else {
+            panic!("expected Type, got {:#?}; self is {:#?}", other.token, self.token);
+          }
+        }
+        Token::Attribute(token) => {
+          if let Some(attr) = other.token.as_attribute() {
+            token.ident.assert_same_token(&attr.ident);
+            if let Some(type_expr) = &token.type_expr {
+              type_expr.assert_same_token_if_some(&attr.type_expr);
+            } else {
+              assert!(attr.type_expr.is_none())
+            }
+            token.expr.assert_same_token(&attr.expr);
+          } else {
+            panic!("expected Attribute, got {:#?}; self is {:#?}", other.token, self.token);
+          }
+        }
+        Token::Match(token) => {
+          if let Some(m) = other.token.as_match() {
+            token.subject.assert_same_token(&m.subject);
+            assert_eq!(token.arms.len(), m.arms.len());
+            for (i, arm) in token.arms.iter().enumerate() {
+              arm.assert_same_token(&m.arms[i]);
+            }
+          } else {
+            panic!("expected Match, got {:#?}; self is {:#?}", other.token, self.token);
+          }
+        }
+        Token::MatchArm(token) => {
+          if let Some(arm) = other.token.as_match_arm() {
+            assert_eq!(token.patterns.len(), arm.patterns.len());
+            for (i, pattern) in token.patterns.iter().enumerate() {
+              pattern.assert_same_token(&arm.patterns[i]);
+            }
+            if let Some(guard) = &token.guard {
+              guard.assert_same_token_if_some(&arm.guard);
+            } else {
+              assert!(arm.guard.is_none())
+            }
+            token.result.assert_same_token(&arm.result);
+          } else {
+            panic!("expected MatchArm, got {:#?}; self is {:#?}", other.token, self.token);
+          }
+        }
+        Token::Range(token) => {
+          if let Some(range) = other.token.as_range() {
+            token.start.assert_same_token(&range.start);
+            token.end.assert_same_token(&range.end);
+            assert_eq!(token.inclusive, range.inclusive);
+          } else {
+            panic!("expected Range, got {:#?}; self is {:#?}", other.token, self.token);
+          }
+        }
         Token::Option(token) => {
           if let Some(cond) = other.token.as_option() {
             token.key.assert_same_token(&cond.key);
@@ -1,7 +1,22 @@
-use nom::{branch::alt, character::complete::char, combinator::map, sequence::terminated};
+use nom::{
+  branch::alt,
+  bytes::complete::take_while,
+  character::complete::{alpha1, char, space1},
+  combinator::{map, recognize, verify},
+  error::ErrorKind,
+  sequence::{pair, preceded, terminated},
+  Err,
+};
 use nom_tracable::tracable_parser;
 
-use crate::{n, Node, Result, Span, Token};
+use crate::{
+  denomination_decimals, n, since, token_failure, valid_ident_char_a, Amount, Node, Result, Span, Token, KEYWORDS,
+};
+
+/// Units that can be attached to a number without a space, e.g. `30bps`. Any other
+/// unit must be separated by whitespace (`250 USDC`) so that identifiers starting
+/// with numbers, such as `1foo_v1`, keep working.
+pub const ATTACHED_UNITS: [&str; 4] = ["bps", "wei", "gwei", "ether"];
 
 #[tracable_parser]
 pub fn number(i: Span) -> Result {
@@ -13,9 +28,44 @@ pub fn percentage(i: Span) -> Result {
   map(terminated(n, char('%')), |pct| Node::new(Token::Percentage(pct), &i))(i)
 }
 
+#[tracable_parser]
+fn unit(i: Span) -> Result<Span, String> {
+  let (rest, span) = recognize(pair(alpha1, take_while(valid_ident_char_a)))(i)?;
+  let unit = span.fragment().to_lowercase();
+  // keywords may follow a number, e.g. `x > 1 and y`, so can never be units
+  if KEYWORDS.contains(&unit.as_str()) {
+    return Err(Err::Error((i, ErrorKind::Tag)));
+  }
+
+  Ok((rest, unit))
+}
+
+#[tracable_parser]
+fn attached_unit(i: Span) -> Result<Span, String> {
+  verify(unit, |unit: &str| ATTACHED_UNITS.contains(&unit))(i)
+}
+
+#[tracable_parser]
+pub fn amount(i: Span) -> Result {
+  let (rest, (value, unit)) = pair(n, alt((attached_unit, preceded(space1, unit))))(i)?;
+
+  // known denominations are scaled exactly; e.g. fractional wei is an error
+  let base_value = match denomination_decimals(&unit) {
+    Some(decimals) => Some(value.scale(decimals).map_err(|e| token_failure(i, e))?),
+    None => None,
+  };
+
+  let amount = Amount {
+    value,
+    unit,
+    base_value,
+  };
+  Ok((rest, Node::new(Token::Amount(amount), &i)))
+}
+
 #[tracable_parser]
 pub fn numeric(i: Span) -> Result {
-  alt((percentage, number))(i)
+  alt((percentage, since(2, amount), number))(i)
 }
 
 #[cfg(test)]
@@ -24,6 +74,7 @@ mod test {
   use crate::*;
   use nom_tracable::TracableInfo;
   use rstest::rstest;
+  use serde_test::{assert_ser_tokens, Token as SerdeToken};
 
   #[rstest(input, expected,
       case("1.23", number!(1.23)),
@@ -69,4 +120,107 @@ mod test {
 
     Ok(())
   }
+
+  #[rstest(input, expected,
+      case("1.5 ETH", amount!(1.5, "eth")),
+      case("20 gwei", amount!(20, "gwei", 20_000_000_000)),
+      case("250 USDC", amount!(250, "usdc")),
+      case("-1_000.5\tusdc", amount!(-1_000.5, "usdc")),
+      case("1e3 wbtc", amount!(1000, "wbtc")),
+      case("10 usdc_e", amount!(10, "usdc_e")),
+      case("30bps", amount!(30, "bps")),
+      case("30 BPS", amount!(30, "bps")),
+      case("1.5ether", amount!(1.5, "ether", 1_500_000_000_000_000_000)),
+      case("20GWEI", amount!(20, "gwei", 20_000_000_000)),
+      case("1e9wei", amount!(1_000_000_000, "wei", 1_000_000_000)),
+      case("1.000 wei", amount!(1.0, "wei", 1)),
+      case("10 ether", amount!(10, "ether", "10000000000000000000".parse::<N>().unwrap())),
+      case("0.000000001 Ether", amount!(0.000000001, "ether", 1_000_000_000)),
+    )]
+  fn test_amount(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let span = Span::new_extra(input, info);
+    let (span, node) = numeric(span)?;
+    assert_eq!(span.fragment().len(), 0);
+    node.assert_same_token(&node!(expected));
+
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    rest,
+    case("1foo_v1", "1foo_v1"),
+    case("1dent", "1dent"),
+    case("1gwei_pool", "1gwei_pool"),
+    case("30bps2", "30bps2"),
+    case("1 and 0", " and 0"),
+    case("1 OR 0", " OR 0"),
+    case("1 in [1]", " in [1]"),
+    case("1 not in [1]", " not in [1]"),
+    case("1 as x", " as x"),
+    case("1 between 0 and 2", " between 0 and 2"),
+    case("1 catch", " catch"),
+    case("1.5eth", "1.5eth")
+  )]
+  fn test_amount_invalid(input: &'static str, rest: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(amount(span).is_err());
+
+    // whatever isn't an amount is left for the caller to parse
+    match numeric(span) {
+      Ok((span, _)) => assert_eq!(*span.fragment(), rest),
+      Err(_) => assert_eq!(input, rest),
+    }
+  }
+
+  #[rstest(
+    input,
+    case("1.5 wei"),
+    case("1.5wei"),
+    case("0.0000000001 gwei"),
+    case("0.1e-18 ether")
+  )]
+  fn test_amount_fractional_base_unit(input: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(matches!(amount(span), Err(nom::Err::Failure(_))));
+  }
+
+  #[rstest(
+    input,
+    expected,
+    case("fun(1.5 wei)", "1.5 is not a whole number of base units"),
+    case(
+      "fun(1e60 ether)",
+      "1000000000000000000000000000000000000000000000000000000000000 is too large to scale into base units"
+    )
+  )]
+  fn test_amount_error_message(input: &'static str, expected: &str) {
+    let error = parse(input).unwrap_err();
+    assert_eq!(error.to_string(), expected);
+  }
+
+  #[test]
+  fn test_serialize_amount() {
+    assert_ser_tokens(
+      &amount!(1.5, "eth"),
+      &[
+        SerdeToken::NewtypeVariant {
+          name: "Token",
+          variant: "amount",
+        },
+        SerdeToken::Struct { name: "Amount", len: 3 },
+        SerdeToken::Str("value"),
+        SerdeToken::NewtypeVariant {
+          name: "number",
+          variant: "decimal",
+        },
+        SerdeToken::Str("1.5"),
+        SerdeToken::Str("unit"),
+        SerdeToken::Str("eth"),
+        SerdeToken::Str("base_value"),
+        SerdeToken::None,
+        SerdeToken::StructEnd,
+      ],
+    );
+  }
 }
@@ -3,16 +3,19 @@ use std::rc::Rc;
 use nom::{
   branch::alt,
   bytes::complete::{is_a, tag, tag_no_case},
-  character::complete::{anychar, char, digit1, space0, space1},
-  combinator::{map, not, recognize},
+  character::complete::{anychar, char, digit1, satisfy, space0, space1},
+  combinator::{map, not, opt, recognize},
   error::ErrorKind,
-  sequence::{terminated, tuple},
+  sequence::{delimited, pair, terminated, tuple},
   Err,
 };
 use nom_locate::position;
 use nom_tracable::tracable_parser;
 
-use crate::{expr_term, BinaryOp, Conditional, Node, Operator, Result, Span, Token, UnaryOp};
+use crate::{
+  expr_term, grammar_version, since, unpaired_term, valid_ident_char_a, Between, BinaryOp, Conditional, Node, Operator,
+  Result, Span, Token, UnaryOp,
+};
 
 #[tracable_parser]
 pub fn sign(i: Span) -> Result {
@@ -31,15 +34,23 @@ fn negation(i: Span) -> Result {
   })(i)
 }
 
+#[tracable_parser]
+fn bitwise_not(i: Span) -> Result {
+  map(tag("~"), |span: Span| {
+    Node::new(Token::Operator(Operator::BitNot), &span)
+  })(i)
+}
+
 #[tracable_parser]
 fn unary_operator(i: Span) -> Result {
-  alt((negation, terminated(sign, not(digit1))))(i)
+  alt((negation, since(2, bitwise_not), terminated(sign, not(digit1))))(i)
 }
 
 #[tracable_parser]
 fn arithmetic_operator(i: Span) -> Result {
   let (i, start) = position(i)?;
   let (i, c) = anychar(i)?;
+  let next = i.fragment().chars().next();
 
   let op = match c {
     '+' => Ok(Operator::Plus),
@@ -48,6 +59,9 @@ fn arithmetic_operator(i: Span) -> Result {
     '/' => Ok(Operator::Divide),
     '%' => Ok(Operator::Modulus),
     '^' => Ok(Operator::Exponent),
+    // not `&&`, `||` or `|>`
+    '&' if next != Some('&') && grammar_version() >= 2 => Ok(Operator::BitAnd),
+    '|' if next != Some('|') && next != Some('>') && grammar_version() >= 2 => Ok(Operator::BitOr),
     _ => Err(Err::Error((i, ErrorKind::Char))),
   }?;
 
@@ -85,13 +99,29 @@ fn comparison_operator(i: Span) -> Result {
   Ok((i, Node::new(Token::Operator(op), &span)))
 }
 
+/// Operators of more than one character that would otherwise be read as a
+/// single character one, e.g. `//` as `/` or `>>` as `>`
 #[tracable_parser]
 fn other_operator(i: Span) -> Result {
-  let (i, span) = is_a("+-|>")(i)?;
-  let op = match *span.fragment() {
+  let (i, span) = alt((
+    is_a("+-|>"),
+    since(
+      2,
+      alt((
+        tag("//"),
+        tag("<<"),
+        terminated(tag_no_case("xor"), not(satisfy(valid_ident_char_a))),
+      )),
+    ),
+  ))(i)?;
+  let op = match span.fragment().to_lowercase().as_str() {
     "++" => Ok(Operator::Concatenate),
     "--" => Ok(Operator::Subtract),
     "|>" => Ok(Operator::Pipe),
+    ">>" if grammar_version() >= 2 => Ok(Operator::ShiftRight),
+    "//" => Ok(Operator::IntDivide),
+    "<<" => Ok(Operator::ShiftLeft),
+    "xor" => Ok(Operator::BitXor),
     _ => Err(Err::Error((i, ErrorKind::IsA))),
   }?;
 
@@ -99,7 +129,7 @@ fn other_operator(i: Span) -> Result {
 }
 
 #[tracable_parser]
-fn membership_operator(i: Span) -> Result {
+pub(crate) fn membership_operator(i: Span) -> Result {
   map(
     alt((
       tag_no_case("in"),
@@ -116,11 +146,21 @@ fn membership_operator(i: Span) -> Result {
   )(i)
 }
 
+/// `a() else b()` falls back to `b()` when `a()` fails
+#[tracable_parser]
+fn fallback_operator(i: Span) -> Result {
+  map(
+    terminated(tag_no_case("else"), not(satisfy(valid_ident_char_a))),
+    |span: Span| Node::new(Token::Operator(Operator::Else), &span),
+  )(i)
+}
+
 #[tracable_parser]
 pub fn binary_operator(i: Span) -> Result {
   alt((
     other_operator,
     membership_operator,
+    since(2, fallback_operator),
     arithmetic_operator,
     comparison_operator,
     logic_operator,
@@ -139,44 +179,120 @@ pub fn unary_operation(i: Span) -> Result {
   })(i)
 }
 
-#[tracable_parser]
-pub fn binary_operation(i: Span) -> Result {
-  map(
-    tuple((expr_term, space0, binary_operator, space0, expr_term)),
-    move |(left, _, op, _, right)| {
-      let left = Rc::new(left);
-      let binop = BinaryOp {
+/// What follows the left-hand term of a binary, ternary or `between` operation
+enum Tail {
+  Binary(Node, Node),
+  Ternary(Node, Node),
+  Between(bool, Node, Node),
+}
+
+impl Tail {
+  fn apply(self, left: Node) -> Node {
+    let left = Rc::new(left);
+    let token = match self {
+      Tail::Binary(op, right) => Token::BinaryOp(BinaryOp {
         operator: Rc::new(op),
         left: Rc::clone(&left),
         right: Rc::new(right),
-      };
+      }),
+      Tail::Ternary(if_true, if_false) => Token::Conditional(Conditional {
+        condition: Rc::clone(&left),
+        if_true: Rc::new(if_true),
+        if_false: Some(Rc::new(if_false)),
+      }),
+      Tail::Between(negated, low, high) => Token::Between(Between {
+        value: Rc::clone(&left),
+        low: Rc::new(low),
+        high: Rc::new(high),
+        negated,
+      }),
+    };
+    Node::from_node(token, &left)
+  }
+}
 
-      Node::from_node(Token::BinaryOp(binop), &left)
-    },
+#[tracable_parser]
+fn binary_tail(i: Span) -> Result<Span, Tail> {
+  map(
+    tuple((space0, binary_operator, space0, expr_term)),
+    |(_, op, _, right)| Tail::Binary(op, right),
   )(i)
 }
 
 #[tracable_parser]
-pub fn ternary_operator(i: Span) -> Result {
+fn ternary_tail(i: Span) -> Result<Span, Tail> {
   let qm = recognize(tuple((space0, char('?'), space0)));
-  let colon = recognize(tuple((space0, char(':'), space0)));
+  let colon = || recognize(tuple((space0, char(':'), space0)));
   map(
-    tuple((expr_term, qm, expr_term, colon, expr_term)),
-    |(cond, _, left, _, right)| {
-      let cond = Rc::new(cond);
-      let c = Conditional {
-        condition: Rc::clone(&cond),
-        if_true: Rc::new(left),
-        if_false: Some(Rc::new(right)),
-      };
-      Node::from_node(Token::Conditional(c), &cond)
-    },
+    tuple((
+      qm,
+      // `c ? a:b` is a ternary rather than a trading pair
+      alt((terminated(expr_term, colon()), terminated(unpaired_term, colon()))),
+      expr_term,
+    )),
+    |(_, if_true, if_false)| Tail::Ternary(if_true, if_false),
   )(i)
 }
 
+/// `between low and high` or `not between low and high`, the `and` is part of
+/// the operation rather than a logical and
+#[tracable_parser]
+fn between_tail(i: Span) -> Result<Span, Tail> {
+  map(
+    tuple((
+      space1,
+      opt(terminated(tag_no_case("not"), space1)),
+      terminated(tag_no_case("between"), space1),
+      expr_term,
+      delimited(space1, tag_no_case("and"), space1),
+      expr_term,
+    )),
+    |(_, not, _, low, _, high)| Tail::Between(not.is_some(), low, high),
+  )(i)
+}
+
+/// Every operation but a unary one starts with a term, which is parsed only once
+/// before trying what may follow it. Parsing it again for each kind of operation
+/// would make parse time grow exponentially with nesting.
+#[tracable_parser]
+fn operation_tail(i: Span) -> Result<Span, Tail> {
+  alt((since(2, between_tail), binary_tail, ternary_tail))(i)
+}
+
+#[tracable_parser]
+pub fn binary_operation(i: Span) -> Result {
+  map(pair(expr_term, binary_tail), |(left, tail)| tail.apply(left))(i)
+}
+
+#[tracable_parser]
+pub fn ternary_operator(i: Span) -> Result {
+  map(pair(expr_term, ternary_tail), |(left, tail)| tail.apply(left))(i)
+}
+
+/// `x between low and high` or `x not between low and high`
+#[tracable_parser]
+pub fn between_operation(i: Span) -> Result {
+  map(pair(expr_term, between_tail), |(left, tail)| tail.apply(left))(i)
+}
+
 #[tracable_parser]
 pub fn operation(i: Span) -> Result {
-  alt((unary_operation, binary_operation, ternary_operator))(i)
+  alt((
+    unary_operation,
+    map(pair(expr_term, operation_tail), |(left, tail)| tail.apply(left)),
+  ))(i)
+}
+
+/// An operation or a lone term
+#[tracable_parser]
+pub(crate) fn operation_or_term(i: Span) -> Result {
+  alt((
+    unary_operation,
+    map(pair(expr_term, opt(operation_tail)), |(left, tail)| match tail {
+      Some(tail) => tail.apply(left),
+      None => left,
+    }),
+  ))(i)
 }
 
 #[cfg(test)]
@@ -197,7 +313,9 @@ mod test {
         case("!true", node!(unary_op!("!", boolean!(true)))),
         case("not true", node!(unary_op!("!", boolean!(true)))),
         case("![1, true, false]", node!(unary_op!("!", list!(number!(1), boolean!(true), boolean!(false))))),
-        case("not   [1, true, false]", node!(unary_op!("!", list!(number!(1), boolean!(true), boolean!(false)))))
+        case("not   [1, true, false]", node!(unary_op!("!", list!(number!(1), boolean!(true), boolean!(false))))),
+        case("~flags", node!(unary_op!("~", ident!("flags")))),
+        case("~ 0xff", node!(unary_op!("~", number!(255))))
     )]
   fn test_unary_op(input: &'static str, expected: Node, info: TracableInfo) -> Result {
     let span = Span::new_extra(input, info);
@@ -291,6 +409,33 @@ mod test {
           case("1 not    in [1,2,3]", node!(binary_op!(number!(1), "not in", list!(number!(1), number!(2), number!(3))))),
           case("-1.1 not in [-1.1,2,3]", node!(binary_op!(number!(-1.1), "not in", list!(number!(-1.1), number!(2), number!(3))))),
           case("1 not in foo()", node!(binary_op!(number!(1), "not in", function!("foo")))),
+          case(
+            r#"swap(usdc, eth) else notify("swap failed")"#,
+            node!(
+              binary_op!(
+                function!("swap", none, ident!("usdc"), ident!("eth")),
+                "else",
+                function!("notify", none, string!("swap failed"))
+              )
+            )
+          ),
+          case("a ELSE b", node!(binary_op!(ident!("a"), "else", ident!("b")))),
+          case("1 else 2", node!(binary_op!(number!(1), "else", number!(2)))),
+          case("flags & 0x01", node!(binary_op!(ident!("flags"), "&", number!(1)))),
+          case("a&&b", node!(binary_op!(ident!("a"), "&&", ident!("b")))),
+          case("flags | 0b10", node!(binary_op!(ident!("flags"), "|", number!(2)))),
+          case("a||b", node!(binary_op!(ident!("a"), "||", ident!("b")))),
+          case("a|b", node!(binary_op!(ident!("a"), "|", ident!("b")))),
+          case("a |> b()", node!(binary_op!(ident!("a"), "|>", function!("b")))),
+          case("a XOR b", node!(binary_op!(ident!("a"), "xor", ident!("b")))),
+          case("1 xor 2", node!(binary_op!(number!(1), "xor", number!(2)))),
+          case("1 << 96", node!(binary_op!(number!(1), "<<", number!(96)))),
+          case("1<=96", node!(binary_op!(number!(1), "<=", number!(96)))),
+          case("x >> 2", node!(binary_op!(ident!("x"), ">>", number!(2)))),
+          case("x>=2", node!(binary_op!(ident!("x"), ">=", number!(2)))),
+          case("x>2", node!(binary_op!(ident!("x"), ">", number!(2)))),
+          case("tick // spacing", node!(binary_op!(ident!("tick"), "//", ident!("spacing")))),
+          case("tick/spacing", node!(binary_op!(ident!("tick"), "/", ident!("spacing")))),
           case(
             "(1 in foo()) or (2 not in bar)",
             node!(
@@ -318,6 +463,35 @@ mod test {
 
     Ok(())
   }
+  #[rstest(input, expected,
+        case(
+          "price(eth) between 1800 and 2000",
+          between!(function!("price", none, ident!("eth")), number!(1800), number!(2000))
+        ),
+        case("x NOT BETWEEN 1% and 5%", between!(ident!("x"), percentage!(1), percentage!(5), negated)),
+        case("x between (a and b) and c", between!(ident!("x"), binary_op!(ident!("a"), "&&", ident!("b")), ident!("c"))),
+        case("1 between 0 and 2", between!(number!(1), number!(0), number!(2))),
+    )]
+  fn test_between_operation(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let span = Span::new_extra(input, info);
+    let (span, node) = between_operation(span)?;
+    assert!(span.fragment().is_empty());
+    node.assert_same_token(&node!(expected));
+
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("x between 1"),
+    case("x between 1 && 2"),
+    case("x betweenness 1 and 2"),
+    case("x between1 and 2")
+  )]
+  fn test_between_operation_invalid(input: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(nom::combinator::all_consuming(between_operation)(span).is_err());
+  }
 
   #[rstest(input, expected,
         case(r#""string" ? true : false"#, conditional!(string!("string"), boolean!(true), boolean!(false))),
@@ -325,6 +499,10 @@ mod test {
             r#"func("input") ? [1] : [2]"#,
             conditional!(function!("func", none, string!("input")), list!(number!(1)), list!(number!(2)))
         ),
+        case("c ? a:b", conditional!(ident!("c"), ident!("a"), ident!("b"))),
+        case("c ? 1:2", conditional!(ident!("c"), number!(1), number!(2))),
+        case("c ? true:false", conditional!(ident!("c"), boolean!(true), boolean!(false))),
+        case("c ? eth:usdc : none", conditional!(ident!("c"), pair!(ident!("eth"), ident!("usdc")), ident!("none"))),
     )]
   fn test_ternary_op(input: &'static str, expected: Token, info: TracableInfo) -> Result {
     let input = Span::new_extra(input, info);
@@ -340,4 +518,18 @@ mod test {
 
     Ok(())
   }
+
+  #[test]
+  fn test_ternary_op_pair_else() -> Result {
+    let input = Span::new_extra("c ? a : eth:usdc", info());
+    let (span, node) = ternary_operator(input)?;
+    assert!(span.fragment().is_empty());
+    node.assert_same_token(&node!(conditional!(
+      ident!("c"),
+      ident!("a"),
+      pair!(ident!("eth"), ident!("usdc"))
+    )));
+
+    Ok(())
+  }
 }
@@ -0,0 +1,77 @@
+use std::rc::Rc;
+
+use nom::{
+  branch::alt,
+  character::complete::char,
+  combinator::{map, verify},
+  sequence::separated_pair,
+};
+use nom_tracable::tracable_parser;
+
+use crate::{address, handle, identifier, Node, Pair, Result, Span, Token};
+
+/// Either side of a pair is an address, a handle or a symbol; identifiers made up
+/// of only digits, e.g. in `c ? 1:2`, aren't symbols
+#[tracable_parser]
+fn pair_side(i: Span) -> Result {
+  alt((
+    address,
+    handle,
+    verify(identifier, |node: &Node| match node.token.as_identifier() {
+      Some(s) => !s.chars().all(|c| c.is_ascii_digit() || c == '_'),
+      None => false,
+    }),
+  ))(i)
+}
+
+/// Trading pairs such as `ETH:USDC`. Unlike `ETH/USDC` this is never arithmetic,
+/// and no whitespace is allowed around the `:` so that ternaries still work.
+#[tracable_parser]
+pub fn trading_pair(i: Span) -> Result {
+  map(separated_pair(pair_side, char(':'), pair_side), |(base, quote)| {
+    let base = Rc::new(base);
+    let pair = Pair {
+      base: Rc::clone(&base),
+      quote: Rc::new(quote),
+    };
+    Node::from_node(Token::Pair(pair), &base)
+  })(i)
+}
+
+#[cfg(test)]
+mod test {
+  use crate::test::{info, Result};
+  use crate::*;
+  use nom_tracable::TracableInfo;
+  use rstest::rstest;
+
+  #[rstest(input, expected,
+      case("ETH:USDC", pair!(ident!("eth"), ident!("usdc"))),
+      case("wbtc:eth", pair!(ident!("wbtc"), ident!("eth"))),
+      case("1INCH:USDC", pair!(ident!("1inch"), ident!("usdc"))),
+      case(
+        "0xcac725bef4f114f728cbcfd744a731c2a463c3fc:usdc",
+        pair!(address!("0xcac725bef4f114f728cbcfd744a731c2a463c3fc"), ident!("usdc"))
+      ),
+    )]
+  fn test_trading_pair(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let (span, node) = trading_pair(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    node.assert_same_token(&node!(expected));
+
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("ETH"),
+    case("ETH : USDC"),
+    case("ETH:"),
+    case("1:2"),
+    case("eth:1_0"),
+    case("\"eth\":usdc")
+  )]
+  fn test_trading_pair_invalid(input: &'static str, info: TracableInfo) {
+    assert!(trading_pair(Span::new_extra(input, info)).is_err());
+  }
+}
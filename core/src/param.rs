@@ -0,0 +1,155 @@
+use std::rc::Rc;
+
+use nom::{
+  bytes::complete::tag_no_case,
+  character::complete::{char, space0, space1},
+  combinator::{map, opt},
+  sequence::{preceded, terminated, tuple},
+};
+use nom_tracable::tracable_parser;
+
+use crate::{expression, identifier, type_annotation, Node, Param, Result, Span, Token};
+
+/// `param amount: number = 100` declares an input of the script, with an
+/// optional type and default. Parameters are only allowed at the top of a file.
+#[tracable_parser]
+pub fn param_declaration(i: Span) -> Result {
+  map(
+    tuple((
+      terminated(tag_no_case("param"), space1),
+      identifier,
+      opt(type_annotation),
+      opt(preceded(tuple((space0, char('='), space0)), expression)),
+    )),
+    |(keyword, name, type_expr, default)| {
+      let param = Param {
+        name: Rc::new(name),
+        type_expr: type_expr.map(Rc::new),
+        default: default.map(Rc::new),
+      };
+      Node::new(Token::Param(param), &keyword)
+    },
+  )(i)
+}
+
+/// A typed parameter of a definition, e.g. the `b: list<address>` in
+/// `def f(a, b: list<address>) { ... }`
+#[tracable_parser]
+pub(crate) fn typed_param(i: Span) -> Result {
+  map(
+    tuple((
+      identifier,
+      type_annotation,
+      opt(preceded(tuple((space0, char('='), space0)), expression)),
+    )),
+    |(name, type_expr, default)| {
+      let name = Rc::new(name);
+      let param = Param {
+        name: Rc::clone(&name),
+        type_expr: Some(Rc::new(type_expr)),
+        default: default.map(Rc::new),
+      };
+      Node::from_node(Token::Param(param), &name)
+    },
+  )(i)
+}
+
+/// The parameters a script declares. As they're only allowed at the top of a
+/// file, only the leading statements of `tree` are looked at. In a tree from
+/// `parse_file` these are the statements of the entry file, which come last,
+/// so parameters of imported files aren't included.
+pub fn params(tree: &[Node]) -> Vec<&Node> {
+  let entry = tree.last().map(|node| &node.source);
+  tree
+    .iter()
+    .filter(|node| Some(&node.source) == entry)
+    .take_while(|node| matches!(node.token, Token::Param(_) | Token::Import(_)))
+    .filter(|node| node.token.as_param().is_some())
+    .collect()
+}
+
+#[cfg(test)]
+mod test {
+  use crate::test::{info, Result};
+  use crate::*;
+  use nom::combinator::all_consuming;
+  use nom_tracable::TracableInfo;
+  use rstest::rstest;
+
+  #[rstest(input, expected,
+      case("param amount: number = 100", param!("amount", type_expr!("number"), number!(100))),
+      case("param token: address", param!("token", type_expr!("address"), none)),
+      case("PARAM Slippage=0.5%", param!("slippage", none, percentage!(0.5))),
+      case("param interval", param!("interval", none, none)),
+      case(
+        "param pair:pair = eth:usdc",
+        param!("pair", type_expr!("pair"), pair!(ident!("eth"), ident!("usdc")))
+      ),
+      case(
+        "param recipients: list<address>? = none",
+        param!("recipients", type_expr!("list", [type_expr!("address")], optional), ident!("none"))
+      ),
+    )]
+  fn test_param_declaration(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let (span, node) = param_declaration(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    node.assert_same_token(&node!(expected));
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("param"),
+    case("param amount number"),
+    case("param amount: = 1"),
+    case("params amount")
+  )]
+  fn test_param_declaration_invalid(input: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(all_consuming(param_declaration)(span).is_err());
+  }
+
+  #[test]
+  fn test_params() -> Result {
+    let tree = parse(
+      r#"# Inputs
+      param token: address
+      import "lib.vxl"
+      param amount: number = 100
+
+      buy(token, amount)"#,
+    )?;
+
+    let declared = params(&tree);
+    assert_eq!(declared.len(), 2);
+    declared[0].assert_same_token(&node!(param!("token", type_expr!("address"), none)));
+    declared[1].assert_same_token(&node!(param!("amount", type_expr!("number"), number!(100))));
+    assert_eq!(declared[1].line, 4);
+
+    assert!(params(&parse("buy(eth)")?).is_empty());
+    Ok(())
+  }
+
+  #[test]
+  fn test_params_with_imports() -> Result {
+    let loader: std::collections::HashMap<String, String> = [
+      ("main.vxl", "import \"lib.vxl\"\nparam amount: number\n\nbuy(amount)"),
+      ("lib.vxl", "param slippage = 0.5%\n\nfun()"),
+    ]
+    .iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+
+    let tree = parse_file("main.vxl", &loader)?.tree;
+    let declared = params(&tree);
+    assert_eq!(declared.len(), 1);
+    declared[0].assert_same_token(&node!(param!("amount", type_expr!("number"), none)));
+    assert_eq!(declared[0].source.as_deref(), Some("main.vxl"));
+    Ok(())
+  }
+
+  #[rstest(input, case("buy(eth)\nparam amount"), case("when ready { param amount }"))]
+  fn test_param_not_at_top(input: &'static str) {
+    assert!(parse(input).is_err());
+  }
+}
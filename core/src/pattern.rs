@@ -0,0 +1,162 @@
+use std::rc::Rc;
+
+use nom::{
+  branch::alt,
+  bytes::complete::{tag, tag_no_case},
+  character::complete::{char, line_ending, multispace0, satisfy, space0, space1},
+  combinator::{map, not, opt, recognize},
+  multi::separated_list1,
+  sequence::{delimited, pair, preceded, terminated, tuple},
+};
+use nom_locate::position;
+use nom_tracable::tracable_parser;
+
+use crate::{body, expression, literal, valid_ident_char_a, Match, MatchArm, Node, Range, Result, Span, Token};
+
+#[tracable_parser]
+fn wildcard(i: Span) -> Result {
+  map(terminated(tag("_"), not(satisfy(valid_ident_char_a))), |span: Span| {
+    Node::new(Token::Wildcard, &span)
+  })(i)
+}
+
+/// `1..10`, or `1..=10` to include the end
+#[tracable_parser]
+fn range(i: Span) -> Result {
+  map(
+    tuple((
+      literal,
+      delimited(space0, pair(tag(".."), opt(char('='))), space0),
+      literal,
+    )),
+    |(start, (_, inclusive), end)| {
+      let start = Rc::new(start);
+      let range = Range {
+        start: Rc::clone(&start),
+        end: Rc::new(end),
+        inclusive: inclusive.is_some(),
+      };
+      Node::from_node(Token::Range(range), &start)
+    },
+  )(i)
+}
+
+#[tracable_parser]
+fn pattern(i: Span) -> Result {
+  alt((wildcard, range, literal))(i)
+}
+
+/// `"x" | "y" if guard => result`, the result being an expression or a block
+#[tracable_parser]
+fn match_arm(i: Span) -> Result {
+  let (i, start) = position(i)?;
+  map(
+    tuple((
+      separated_list1(tuple((space0, char('|'), space0)), pattern),
+      opt(preceded(tuple((space1, tag_no_case("if"), space1)), expression)),
+      delimited(space0, tag("=>"), multispace0),
+      alt((body, expression)),
+    )),
+    move |(patterns, guard, _, result)| {
+      let arm = MatchArm {
+        patterns,
+        guard: guard.map(Rc::new),
+        result: Rc::new(result),
+      };
+      Node::new(Token::MatchArm(arm), &start)
+    },
+  )(i)
+}
+
+/// Arms are separated by commas or new lines
+#[tracable_parser]
+fn arm_separator(i: Span) -> Result<Span, Span> {
+  recognize(tuple((space0, alt((recognize(char(',')), line_ending)), multispace0)))(i)
+}
+
+/// `match x { 1..10 => a(), "x" | "y" => b(), _ => c() }`, arms are tried in
+/// order and may have `if` guards
+#[tracable_parser]
+pub fn match_expr(i: Span) -> Result {
+  map(
+    tuple((
+      terminated(tag_no_case("match"), space1),
+      expression,
+      delimited(
+        pair(multispace0, char('{')),
+        preceded(multispace0, separated_list1(arm_separator, match_arm)),
+        tuple((opt(preceded(space0, char(','))), multispace0, char('}'))),
+      ),
+    )),
+    |(keyword, subject, arms)| {
+      let m = Match {
+        subject: Rc::new(subject),
+        arms,
+      };
+      Node::new(Token::Match(m), &keyword)
+    },
+  )(i)
+}
+
+#[cfg(test)]
+mod test {
+  use crate::test::{info, Result};
+  use crate::*;
+  use nom::combinator::all_consuming;
+  use nom_tracable::TracableInfo;
+  use rstest::rstest;
+  use std::convert::TryFrom;
+
+  #[rstest(input, expected,
+      case(
+        r#"match x { 1..10 => a(), "x" | "y" => b(), _ => c() }"#,
+        match_expr!(
+          ident!("x"),
+          match_arm!([range!(number!(1), number!(10))], none, function!("a")),
+          match_arm!([string!("x"), string!("y")], none, function!("b")),
+          match_arm!([wildcard!()], none, function!("c"))
+        )
+      ),
+      case(
+        "MATCH price(eth) {\n  0 ..= 1800 if ready => buy(eth)\n  true => {\n    a()\n    b()\n  },\n  _ if x > 1 => 1\n}",
+        match_expr!(
+          function!("price", none, ident!("eth")),
+          match_arm!(
+            [range!(number!(0), number!(1800), inclusive)],
+            ident!("ready"),
+            function!("buy", none, ident!("eth"))
+          ),
+          match_arm!([boolean!(true)], none, body!(function!("a"), function!("b"))),
+          match_arm!([wildcard!()], binary_op!(ident!("x"), ">", number!(1)), number!(1))
+        )
+      ),
+      case(
+        "match x {\n  -1.5 | 1% | 30s => none,\n}",
+        match_expr!(
+          ident!("x"),
+          match_arm!([number!(-1.5), percentage!(1), duration!(30_000)], none, ident!("none"))
+        )
+      ),
+    )]
+  fn test_match_expr(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let (span, node) = match_expr(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    node.assert_same_token(&node!(expected));
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("match x {}"),
+    case("match x { 1 }"),
+    case("match x { 1 => }"),
+    case("match x { _x => a() }"),
+    case("match x { 1 => a() 2 => b() }"),
+    case("match x { 1.. => a() }"),
+    case("matchx { 1 => a() }")
+  )]
+  fn test_match_expr_invalid(input: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(all_consuming(match_expr)(span).is_err());
+  }
+}
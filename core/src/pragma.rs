@@ -0,0 +1,335 @@
+use std::cell::RefCell;
+
+use nom::{
+  branch::alt,
+  bytes::complete::{is_not, tag, tag_no_case},
+  character::complete::{char, line_ending, space0, space1},
+  combinator::{cut, eof, map, recognize},
+  error::ErrorKind,
+  sequence::{delimited, pair, preceded, tuple},
+  Err,
+};
+use nom_tracable::tracable_parser;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{string, Result, Setting, Span, Tree};
+
+/// The grammar version used by scripts without a pragma
+pub const LATEST_VERSION: u32 = 2;
+pub const OLDEST_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum PragmaError {
+  #[error("unsupported VXL version {0:?}, supported versions are {1} to {2}")]
+  UnsupportedVersionError(String, u32, u32),
+
+  #[error("invalid VXL pragma {0:?}, expected e.g. `#!vxl 2` or `pragma vxl = \"2\"`")]
+  InvalidPragmaError(String),
+}
+
+/// Program metadata, e.g. the grammar version from `#!vxl 2`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Metadata {
+  pub version: u32,
+}
+
+impl Default for Metadata {
+  fn default() -> Self {
+    Metadata {
+      version: LATEST_VERSION,
+    }
+  }
+}
+
+/// A parsed script along with its metadata
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Program {
+  pub metadata: Metadata,
+  pub tree: Tree,
+}
+
+thread_local! {
+  static VERSION: RefCell<u32> = const { RefCell::new(LATEST_VERSION) };
+}
+
+/// Runs `f` with `version` as the grammar version parsers check features against
+pub fn with_version<T>(version: u32, f: impl FnOnce() -> T) -> T {
+  let _version = Setting::set(&VERSION, version);
+  f()
+}
+
+pub fn grammar_version() -> u32 {
+  VERSION.with(|v| *v.borrow())
+}
+
+/// Only runs `parser` when the grammar version is at least `version`
+pub(crate) fn since<'a, O>(
+  version: u32,
+  mut parser: impl FnMut(Span<'a>) -> Result<'a, Span<'a>, O>,
+) -> impl FnMut(Span<'a>) -> Result<'a, Span<'a>, O> {
+  move |i: Span<'a>| {
+    if grammar_version() >= version {
+      parser(i)
+    } else {
+      Err(Err::Error((i, ErrorKind::Verify)))
+    }
+  }
+}
+
+#[tracable_parser]
+fn pragma_end(i: Span) -> Result<Span, Span> {
+  preceded(space0, alt((recognize(line_ending), eof)))(i)
+}
+
+/// A version pragma on the first line, either `#!vxl 2` or `pragma vxl = "2"`.
+/// Returns the requested version as written. Anything else starting like a
+/// pragma is a failure rather than being taken for a comment.
+#[tracable_parser]
+pub fn pragma(i: Span) -> Result<Span, String> {
+  alt((
+    preceded(
+      pair(tag("#!"), tag_no_case("vxl")),
+      cut(map(delimited(space1, is_not(" \t\r\n"), pragma_end), |v: Span| {
+        v.fragment().to_string()
+      })),
+    ),
+    preceded(
+      tuple((tag_no_case("pragma"), space1, tag_no_case("vxl"))),
+      cut(map(
+        delimited(tuple((space0, char('='), space0)), string, pragma_end),
+        |v| v.token.as_string().unwrap_or_default().to_string(),
+      )),
+    ),
+  ))(i)
+}
+
+/// Checks a requested version is one this parser supports
+pub fn supported_version(version: &str) -> std::result::Result<u32, PragmaError> {
+  match version.parse() {
+    Ok(v) if (OLDEST_VERSION..=LATEST_VERSION).contains(&v) => Ok(v),
+    _ => Err(PragmaError::UnsupportedVersionError(
+      version.to_string(),
+      OLDEST_VERSION,
+      LATEST_VERSION,
+    )),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::test::{info, Result};
+  use crate::*;
+  use nom_tracable::TracableInfo;
+  use rstest::rstest;
+  use rust_decimal_macros::dec;
+  use std::convert::TryFrom;
+
+  #[rstest(
+    input,
+    expected,
+    case("#!vxl 2", "2"),
+    case("#!VXL 1\nfun()", "1"),
+    case("pragma vxl = \"2\"", "2"),
+    case("PRAGMA VXL=\"1\"  \nfun()", "1"),
+    case("#!vxl two", "two")
+  )]
+  fn test_pragma(input: &'static str, expected: &str, info: TracableInfo) -> Result {
+    let (_, version) = pragma(Span::new_extra(input, info))?;
+    assert_eq!(version, expected);
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("# vxl 2"),
+    case("#!vxl"),
+    case("#!vxl 2 fun()"),
+    case("pragma vxl = 2"),
+    case("fun()\n#!vxl 2")
+  )]
+  fn test_pragma_invalid(input: &'static str, info: TracableInfo) {
+    assert!(pragma(Span::new_extra(input, info)).is_err());
+  }
+
+  #[rstest(
+    input,
+    version,
+    case("fun()", LATEST_VERSION),
+    case("#!vxl 2\nwhen ready { fun() }", 2),
+    case("pragma vxl = \"1\"\nfun()", 1),
+    case("#!vxl 1\n# only a comment", 1)
+  )]
+  fn test_parse_program(input: &'static str, version: u32) -> Result {
+    let program = parse_program(input)?;
+    assert_eq!(program.metadata.version, version);
+    Ok(())
+  }
+
+  #[test]
+  fn test_with_version_restored_on_panic() {
+    let result = std::panic::catch_unwind(|| with_version(1, || panic!("parser bug")));
+    assert!(result.is_err());
+    assert_eq!(grammar_version(), LATEST_VERSION);
+  }
+
+  #[test]
+  fn test_parse_program_location() -> Result {
+    let program = parse_program("#!vxl 2\n\nfun()")?;
+    assert_eq!(program.tree[0].line, 3);
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("#!vxl 1\nwhen ready { fun() }"),
+    case("#!vxl 1\nparam amount"),
+    case("#!vxl 1\nimport \"x.vxl\""),
+    case("#!vxl 1\n@retry(3) fun()"),
+    case("#!vxl 1\n250 USDC"),
+    case("#!vxl 1\nfun(1.5 ether)"),
+    case("#!vxl 1\n115792089237316195423570985008687907853269984665640564039457584007913129639935"),
+    case("#!vxl 1\n1e20"),
+    case("#!vxl 1\nfun(-0x10)"),
+    case("#!vxl 1\nfun(2024-01-01T00:00:00Z)"),
+    case("#!vxl 1\neth:usdc"),
+    case("#!vxl 1\nfun(@treasury)"),
+    case("#!vxl 1\na // b"),
+    case("#!vxl 1\na & b"),
+    case("#!vxl 1\na | b"),
+    case("#!vxl 1\na xor b"),
+    case("#!vxl 1\n~a"),
+    case("#!vxl 1\na << b"),
+    case("#!vxl 1\na >> b"),
+    case("#!vxl 1\nx between 1 and 2"),
+    case("#!vxl 1\nx not between 1 and 2"),
+    case("#!vxl 1\nfun (1)"),
+    case("#!vxl 1\nfun(1,)"),
+    case("#!vxl 1\nfun(\n  1,\n  2,\n)"),
+    case("#!vxl 1\nif (a, b, c)"),
+    case("#!vxl 1\nif(a, b, c,)"),
+    case("#!vxl 1\na() else b()"),
+    case("#!vxl 1\nx = 1")
+  )]
+  fn test_parse_program_v1(input: &'static str) {
+    assert!(parse_program(input).is_err());
+  }
+
+  /// Literals that were identifiers before version 2
+  #[rstest(
+    input,
+    expected,
+    case("#!vxl 1\n0xff", "0xff"),
+    case("#!vxl 1\n30s", "30s"),
+    case("#!vxl 1\n1h", "1h"),
+    case("#!vxl 1\n30bps", "30bps"),
+    case("#!vxl 1\n20gwei", "20gwei")
+  )]
+  fn test_parse_program_v1_identifiers(input: &'static str, expected: &str) -> Result {
+    let program = parse_program(input)?;
+    program.tree[0].assert_same_token(&node!(ident!(expected)));
+    Ok(())
+  }
+
+  /// Version 1 is the grammar from before pragmas, these are its test cases
+  #[rstest(input, expected,
+    case("#!vxl 1\n[1, 2,]", vec![list!(number!(1), number!(2))]),
+    case("#!vxl 1\n[,]", vec![list!()]),
+    case("#!vxl 1\n[, 1]", vec![list!()]),
+    case("#!vxl 1\n[\n  1,\n  2\n]", vec![list!(number!(1), number!(2))]),
+    case(
+      "#!vxl 1\nfun(10000000000000000000)",
+      vec![function!("fun", none, Token::Number(N::Decimal(dec!(10000000000000000000))))]
+    ),
+    case(
+      "#!vxl 1\n-92233720368547758080",
+      vec![Token::Number(N::Decimal(dec!(-92233720368547758080)))]
+    ),
+    case("#!vxl 1\n1.55e1", vec![Token::Number(N::Int(15))]),
+    case("#!vxl 1\n1.7e8", vec![Token::Number(N::Int(170000000))]),
+    case("#!vxl 1\n1e-4", vec![number!(0.0001)]),
+    case("#!vxl 1\nfun(1, 2%, 1dent, 0x)", vec![function!("fun", none, number!(1), percentage!(2), ident!("1dent"), ident!("0x"))]),
+    case("#!vxl 1\nfun(\n  1,\n  2\n)", vec![function!("fun", none, number!(1), number!(2))]),
+    case("#!vxl 1\nfun(not(not_my_label))", vec![function!("fun", none, function!("not", none, ident!("not_my_label")))]),
+    case("#!vxl 1\nif(a, b)", vec![conditional!(ident!("a"), ident!("b"))]),
+    case("#!vxl 1\na |> b", vec![binary_op!(ident!("a"), "|>", ident!("b"))]),
+    case(
+      "#!vxl 1\n[1, 2.0, 3%] ++ [1, 2.0, 3%]",
+      vec![binary_op!(
+        list!(number!(1), number!(2.0), percentage!(3)),
+        "++",
+        list!(number!(1), number!(2.0), percentage!(3))
+      )]
+    ),
+    case(
+      "#!vxl 1\nfun.sub(1, true) # comment 1\n\n1dentifier\n\n1 + 3_000.0_0_01 # comment 2\n\n# comment 3\n\nif(2 >= 1, fun2(), fun3(opt=1))#comment 4",
+      vec![
+        function!("fun", "sub", number!(1), boolean!(true)),
+        ident!("1dentifier"),
+        binary_op!(number!(1), "+", number!(3_000.000_1)),
+        conditional!(
+          binary_op!(number!(2), ">=", number!(1)),
+          function!("fun2"),
+          function!("fun3", none, opt!("opt", number!(1)))
+        )
+      ]
+    ),
+    case("#!vxl 1\nfun(); fun2();", vec![function!("fun"), function!("fun2")]),
+  )]
+  fn test_parse_program_v1_baseline(input: &'static str, expected: Vec<Token>) -> Result {
+    let program = parse_program(input)?;
+    assert_eq!(program.tree.len(), expected.len());
+    for (node, expected) in program.tree.iter().zip(expected) {
+      node.assert_same_token(&node!(expected));
+    }
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    expected,
+    case("#!vxl 3\nfun()", r#"unsupported VXL version "3", supported versions are 1 to 2"#),
+    case(
+      "pragma vxl = \"2.1\"",
+      r#"unsupported VXL version "2.1", supported versions are 1 to 2"#
+    ),
+    case("#!vxl two", r#"unsupported VXL version "two", supported versions are 1 to 2"#)
+  )]
+  fn test_parse_program_unsupported(input: &'static str, expected: &str) {
+    let error = parse_program(input).unwrap_err();
+    assert_eq!(error.to_string(), expected);
+  }
+
+  #[rstest(
+    input,
+    expected,
+    case("#!vxl 2 fun()", "#!vxl 2 fun()"),
+    case("#!vxl", "#!vxl"),
+    case("#!VXL\nfun()", "#!VXL"),
+    case("#!vxl2\nfun()", "#!vxl2"),
+    case("pragma vxl = 2\nfun()", "pragma vxl = 2")
+  )]
+  fn test_parse_program_invalid_pragma(input: &'static str, expected: &str) {
+    let error = parse_program(input).unwrap_err();
+    assert_eq!(
+      error.to_string(),
+      format!(
+        "invalid VXL pragma {:?}, expected e.g. `#!vxl 2` or `pragma vxl = \"2\"`",
+        expected
+      )
+    );
+  }
+
+  /// Both forms of the pragma must be on the first line
+  #[rstest(
+    input,
+    case("fun()\n#!vxl 2"),
+    case("\n#!vxl 2\nfun()"),
+    case("fun() #!vxl 1"),
+    case("fun()\npragma vxl = \"2\""),
+    case("\npragma vxl = \"2\"\nfun()")
+  )]
+  fn test_parse_program_misplaced_pragma(input: &'static str) {
+    assert!(parse_program(input).is_err());
+  }
+}
@@ -0,0 +1,60 @@
+use std::{cell::RefCell, thread::LocalKey};
+
+/// Overrides a thread-local parser setting for as long as it's alive. The
+/// previous value is restored on drop, so also when parsing panics.
+pub(crate) struct Setting<T: 'static> {
+  key: &'static LocalKey<RefCell<T>>,
+  previous: Option<T>,
+}
+
+impl<T> Setting<T> {
+  pub(crate) fn set(key: &'static LocalKey<RefCell<T>>, value: T) -> Self {
+    let previous = key.with(|k| k.replace(value));
+    Setting {
+      key,
+      previous: Some(previous),
+    }
+  }
+}
+
+impl<T> Drop for Setting<T> {
+  fn drop(&mut self) {
+    if let Some(previous) = self.previous.take() {
+      self.key.with(|k| k.replace(previous));
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::panic::catch_unwind;
+
+  thread_local! {
+    static VALUE: RefCell<u32> = const { RefCell::new(1) };
+  }
+
+  #[test]
+  fn test_setting() {
+    {
+      let _two = Setting::set(&VALUE, 2);
+      assert_eq!(VALUE.with(|v| *v.borrow()), 2);
+      {
+        let _three = Setting::set(&VALUE, 3);
+        assert_eq!(VALUE.with(|v| *v.borrow()), 3);
+      }
+      assert_eq!(VALUE.with(|v| *v.borrow()), 2);
+    }
+    assert_eq!(VALUE.with(|v| *v.borrow()), 1);
+  }
+
+  #[test]
+  fn test_setting_restored_on_panic() {
+    let result = catch_unwind(|| {
+      let _two = Setting::set(&VALUE, 2);
+      panic!("parser bug");
+    });
+    assert!(result.is_err());
+    assert_eq!(VALUE.with(|v| *v.borrow()), 1);
+  }
+}
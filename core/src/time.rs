@@ -0,0 +1,299 @@
+use std::convert::TryFrom;
+
+use nom::{
+  branch::alt,
+  bytes::complete::{tag_no_case, take_while, take_while_m_n},
+  character::complete::{char, digit1, one_of, satisfy},
+  combinator::{not, opt, recognize},
+  error::ErrorKind,
+  multi::many1,
+  sequence::{pair, terminated, tuple},
+  Err,
+};
+use nom_tracable::tracable_parser;
+
+use crate::{token_failure, valid_ident_char_a, Node, Result, Span, Token, TokenError, N};
+
+/// Duration units with their length in milliseconds, from longest to shortest.
+/// `ms` must be tried before `m`.
+const DURATION_UNITS: [(&str, u64); 6] = [
+  ("w", 604_800_000),
+  ("d", 86_400_000),
+  ("h", 3_600_000),
+  ("ms", 1),
+  ("m", 60_000),
+  ("s", 1_000),
+];
+
+#[tracable_parser]
+fn duration_unit(i: Span) -> Result<Span, u64> {
+  for (unit, millis) in DURATION_UNITS.iter() {
+    if let Ok((rest, _)) = tag_no_case::<_, _, (Span, ErrorKind)>(*unit)(i) {
+      return Ok((rest, *millis));
+    }
+  }
+
+  Err(Err::Error((i, ErrorKind::Tag)))
+}
+
+/// Durations such as `15m`, `2h30m` or `500ms`, normalised to milliseconds. The
+/// units of compound durations must go from longest to shortest.
+#[tracable_parser]
+pub fn duration(i: Span) -> Result {
+  let (rest, parts) = terminated(
+    many1(pair(
+      recognize(pair(digit1, take_while(|c: char| c.is_ascii_digit() || c == '_'))),
+      duration_unit,
+    )),
+    // e.g. `1d_pool` is an identifier
+    not(satisfy(valid_ident_char_a)),
+  )(i)?;
+
+  let text = || i.fragment()[..rest.location_offset() - i.location_offset()].to_string();
+  let overflow = || token_failure(i, TokenError::DurationOverflowError(text()));
+
+  let mut total: u64 = 0;
+  let mut previous_unit = u64::MAX;
+  for (count, unit) in parts {
+    if unit >= previous_unit {
+      return Err(token_failure(i, TokenError::DurationOrderError(text())));
+    }
+    previous_unit = unit;
+
+    let count: u64 = count.fragment().replace('_', "").parse().map_err(|_| overflow())?;
+    total = count
+      .checked_mul(unit)
+      .and_then(|millis| total.checked_add(millis))
+      .ok_or_else(overflow)?;
+  }
+
+  let millis = i64::try_from(total).map_err(|_| overflow())?;
+  Ok((rest, Node::new(Token::Duration(N::Int(millis)), &i)))
+}
+
+fn digits<'a>(count: usize) -> impl Fn(Span<'a>) -> Result<Span<'a>, Span<'a>> {
+  move |i| take_while_m_n(count, count, |c: char| c.is_ascii_digit())(i)
+}
+
+fn is_leap_year(year: u32) -> bool {
+  (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+  match month {
+    2 if is_leap_year(year) => 29,
+    2 => 28,
+    4 | 6 | 9 | 11 => 30,
+    _ => 31,
+  }
+}
+
+/// RFC 3339 timestamps such as `2026-01-01T00:00:00Z` or
+/// `2026-01-01T09:30:00.250+01:00`
+#[tracable_parser]
+pub fn timestamp(i: Span) -> Result {
+  let (rest, (year, _, month, _, day, _, hour, _, minute, _, second, fraction, offset)) = terminated(
+    tuple((
+      digits(4),
+      char('-'),
+      digits(2),
+      char('-'),
+      digits(2),
+      one_of("Tt"),
+      digits(2),
+      char(':'),
+      digits(2),
+      char(':'),
+      digits(2),
+      opt(recognize(pair(char('.'), digit1))),
+      alt((
+        tag_no_case("z"),
+        recognize(tuple((one_of("+-"), digits(2), char(':'), digits(2)))),
+      )),
+    )),
+    not(satisfy(valid_ident_char_a)),
+  )(i)?;
+
+  // the digit counts are fixed, so these can't fail to parse
+  let field = |s: &str| s.parse::<u32>().unwrap_or(u32::MAX);
+  let (year, month, day) = (field(year.fragment()), field(month.fragment()), field(day.fragment()));
+
+  let offset = offset.fragment().to_uppercase();
+  let valid_offset = offset == "Z" || (field(&offset[1..3]) < 24 && field(&offset[4..]) < 60);
+  let valid_date = (1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day);
+  let valid_time = field(hour.fragment()) < 24
+    && field(minute.fragment()) < 60
+    // allows leap seconds
+    && field(second.fragment()) <= 60;
+
+  let invalid = if !valid_date {
+    Some("no such date")
+  } else if !valid_time {
+    Some("time out of range")
+  } else if !valid_offset {
+    Some("offset out of range")
+  } else {
+    None
+  };
+  if let Some(reason) = invalid {
+    let text = i.fragment()[..rest.location_offset() - i.location_offset()].to_string();
+    return Err(token_failure(i, TokenError::TimestampError(text, reason.to_string())));
+  }
+
+  let ts = format!(
+    "{:04}-{:02}-{:02}T{}:{}:{}{}{}",
+    year,
+    month,
+    day,
+    hour.fragment(),
+    minute.fragment(),
+    second.fragment(),
+    fraction.map_or("", |f| *f.fragment()),
+    offset
+  );
+  Ok((rest, Node::new(Token::Timestamp(ts), &i)))
+}
+
+#[cfg(test)]
+mod test {
+  use crate::test::{info, Result};
+  use crate::*;
+  use nom_tracable::TracableInfo;
+  use rstest::rstest;
+  use serde_test::{assert_ser_tokens, Token as SerdeToken};
+
+  #[rstest(input, expected,
+      case("500ms", duration!(500)),
+      case("30s", duration!(30_000)),
+      case("15m", duration!(900_000)),
+      case("2h", duration!(7_200_000)),
+      case("7d", duration!(604_800_000)),
+      case("2w", duration!(1_209_600_000)),
+      case("2h30m", duration!(9_000_000)),
+      case("1d2h3m4s5ms", duration!(93_784_005)),
+      case("1_000ms", duration!(1_000)),
+      case("90M", duration!(5_400_000)),
+    )]
+  fn test_duration(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let (span, node) = duration(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    assert_eq!(node.token, expected);
+
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("1d_pool"),
+    case("15"),
+    case("15x"),
+    case("2h30"),
+    case("1mx"),
+    case("h")
+  )]
+  fn test_duration_invalid(input: &'static str, info: TracableInfo) {
+    assert!(matches!(
+      duration(Span::new_extra(input, info)),
+      Err(nom::Err::Error(_))
+    ));
+  }
+
+  #[rstest(
+    input,
+    expected,
+    case("30m2h", "30m2h: duration units must go from longest to shortest"),
+    case("1h1h", "1h1h: duration units must go from longest to shortest"),
+    case("1ms1s", "1ms1s: duration units must go from longest to shortest"),
+    case("99999999999999999999d", "99999999999999999999d overflows a duration"),
+    case("9999999999999w", "9999999999999w overflows a duration")
+  )]
+  fn test_duration_failure(input: &'static str, expected: &str, info: TracableInfo) {
+    assert!(matches!(
+      duration(Span::new_extra(input, info)),
+      Err(nom::Err::Failure(_))
+    ));
+    assert_eq!(parse(input).unwrap_err().to_string(), expected);
+  }
+
+  #[rstest(input, expected,
+      case("2026-01-01T00:00:00Z", timestamp!("2026-01-01T00:00:00Z")),
+      case("2026-01-01t00:00:00z", timestamp!("2026-01-01T00:00:00Z")),
+      case("2024-02-29T23:59:60Z", timestamp!("2024-02-29T23:59:60Z")),
+      case("2026-06-30T09:30:00.250+01:00", timestamp!("2026-06-30T09:30:00.250+01:00")),
+      case("2026-12-31T18:00:00-05:30", timestamp!("2026-12-31T18:00:00-05:30")),
+    )]
+  fn test_timestamp(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let (span, node) = timestamp(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    assert_eq!(node.token, expected);
+
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("2026-01-01"),
+    case("2026-01-01T00:00:00"),
+    case("2026-01-01 00:00:00Z"),
+    case("2026-1-01T00:00:00Z"),
+    case("2026-01-01T00:00:00Zulu")
+  )]
+  fn test_timestamp_invalid(input: &'static str, info: TracableInfo) {
+    assert!(matches!(
+      timestamp(Span::new_extra(input, info)),
+      Err(nom::Err::Error(_))
+    ));
+  }
+
+  #[rstest(
+    input,
+    reason,
+    case("2026-13-01T00:00:00Z", "no such date"),
+    case("2026-00-01T00:00:00Z", "no such date"),
+    case("2025-02-29T00:00:00Z", "no such date"),
+    case("2026-04-31T00:00:00Z", "no such date"),
+    case("2026-01-01T24:00:00Z", "time out of range"),
+    case("2026-01-01T00:60:00Z", "time out of range"),
+    case("2026-01-01T00:00:61Z", "time out of range"),
+    case("2026-01-01T00:00:00+24:00", "offset out of range")
+  )]
+  fn test_timestamp_failure(input: &'static str, reason: &str, info: TracableInfo) {
+    assert!(matches!(
+      timestamp(Span::new_extra(input, info)),
+      Err(nom::Err::Failure(_))
+    ));
+    assert_eq!(
+      parse(input).unwrap_err().to_string(),
+      format!("invalid timestamp {}: {}", input, reason)
+    );
+  }
+
+  #[test]
+  fn test_serialize() {
+    assert_ser_tokens(
+      &duration!(900_000),
+      &[
+        SerdeToken::NewtypeVariant {
+          name: "Token",
+          variant: "duration",
+        },
+        SerdeToken::NewtypeVariant {
+          name: "number",
+          variant: "int",
+        },
+        SerdeToken::Str("900000"),
+      ],
+    );
+
+    assert_ser_tokens(
+      &timestamp!("2026-01-01T00:00:00Z"),
+      &[
+        SerdeToken::NewtypeVariant {
+          name: "Token",
+          variant: "timestamp",
+        },
+        SerdeToken::Str("2026-01-01T00:00:00Z"),
+      ],
+    );
+  }
+}
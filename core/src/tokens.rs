@@ -1,6 +1,6 @@
-use std::{convert::TryFrom, fmt, num::ParseIntError, rc::Rc};
+use std::{cell::RefCell, convert::TryFrom, fmt, num::ParseIntError, rc::Rc};
 
-use crate::{Node, N};
+use crate::{ErrorKind, Node, Span, N};
 use paste::paste;
 use rust_decimal::Error as DecimalError;
 use serde::Serialize;
@@ -17,6 +17,52 @@ pub enum TokenError {
 
   #[error("unrecognized operator: {0}")]
   OperatorError(String),
+
+  #[error("{0} is not a whole number of base units")]
+  FractionalBaseUnitError(String),
+
+  #[error("{0} is too large to scale into base units")]
+  ScaleOverflowError(String),
+
+  #[error("{0} is out of the 256-bit integer range")]
+  IntRangeError(String),
+
+  #[error("{0} has too many decimal places")]
+  DecimalRangeError(String),
+
+  #[error("{0}: duration units must go from longest to shortest")]
+  DurationOrderError(String),
+
+  #[error("{0} overflows a duration")]
+  DurationOverflowError(String),
+
+  #[error("invalid timestamp {0}: {1}")]
+  TimestampError(String, String),
+
+  #[error("{0} takes a condition and an optional message, got {1} arguments")]
+  AssertionArgsError(String, usize),
+
+  #[error("return outside of a definition")]
+  ReturnError,
+
+  #[error("invalid directive {0:?}, expected `# vxl-ignore: rule, ...` or `# vxl-ignore-next-line`")]
+  DirectiveError(String),
+}
+
+thread_local! {
+  static TOKEN_ERROR: RefCell<Option<TokenError>> = const { RefCell::new(None) };
+}
+
+/// Fails parsing at `i` because of `error`. Parser errors can't carry it, so it's
+/// kept aside to be reported in place of the failure, see `take_token_error`.
+pub(crate) fn token_failure(i: Span, error: TokenError) -> nom::Err<(Span, ErrorKind)> {
+  TOKEN_ERROR.with(|e| e.replace(Some(error)));
+  nom::Err::Failure((i, ErrorKind::Verify))
+}
+
+/// The error behind the last `token_failure`, if any
+pub(crate) fn take_token_error() -> Option<TokenError> {
+  TOKEN_ERROR.with(|e| e.take())
 }
 
 /// Function call node
@@ -27,6 +73,37 @@ pub struct Function {
   pub args: Vec<Node>,
 }
 
+/// Annotation node, e.g. `@retry(3)`, attached to the statement that follows it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Annotation {
+  pub name: Rc<Node>,
+  pub args: Vec<Node>,
+}
+
+/// Amount node, a number with a unit such as `1.5 ETH` or `30bps`. When the
+/// unit is a known denomination, `base_value` holds the exact integer amount in
+/// base units (e.g. wei).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Amount {
+  pub value: N,
+  pub unit: String,
+  pub base_value: Option<N>,
+}
+
+/// Handle node, a named account such as `@alice.hot`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Handle {
+  pub name: String,
+  pub path: Vec<String>,
+}
+
+/// Trading pair node, e.g. `ETH:USDC`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Pair {
+  pub base: Rc<Node>,
+  pub quote: Rc<Node>,
+}
+
 /// Conditional node
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Conditional {
@@ -35,6 +112,113 @@ pub struct Conditional {
   pub if_false: Option<Rc<Node>>,
 }
 
+/// Trigger block node, `when <condition> { ... }` runs its body whenever the
+/// condition holds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct When {
+  pub condition: Rc<Node>,
+  pub body: Rc<Node>,
+}
+
+/// Trigger block node, `every <interval> { ... }` runs its body on a schedule
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Every {
+  pub interval: Rc<Node>,
+  pub body: Rc<Node>,
+}
+
+/// Atomic block node, the statements in `body` are executed together or not
+/// at all
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Atomic {
+  pub options: Vec<Node>,
+  pub body: Rc<Node>,
+}
+
+/// Assertion node, the condition of an `assert` or `require` statement with
+/// an optional message
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Assertion {
+  pub condition: Rc<Node>,
+  pub message: Option<Rc<Node>>,
+}
+
+/// Try block node, `catch` runs when `body` fails, with the error bound to the
+/// optional `error` identifier
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Try {
+  pub body: Rc<Node>,
+  pub error: Option<Rc<Node>>,
+  pub catch: Rc<Node>,
+}
+
+/// Loop node, `for <binding> in <iterable> { ... }`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct For {
+  pub binding: Rc<Node>,
+  pub iterable: Rc<Node>,
+  pub body: Rc<Node>,
+}
+
+/// Definition node, `def name(param, opt=default) { ... }`. Parameters are
+/// identifiers, or options holding their default value.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Definition {
+  pub name: Rc<Node>,
+  pub params: Vec<Node>,
+  pub body: Rc<Node>,
+}
+
+/// Import node, `import "x.vxl" as x`. `parse_file` puts the imported file's
+/// statements before it and sets `resolved` to the path they have as `source`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Import {
+  pub path: String,
+  pub alias: Option<Rc<Node>>,
+  pub resolved: Option<String>,
+}
+
+/// Parameter node, `param name: type = default`, also used for the typed
+/// parameters of definitions
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Param {
+  pub name: Rc<Node>,
+  pub type_expr: Option<Rc<Node>>,
+  pub default: Option<Rc<Node>>,
+}
+
+/// Type expression node, e.g. `list<address>?`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TypeExpr {
+  pub name: String,
+  pub args: Vec<Node>,
+  pub optional: bool,
+}
+
+/// Match node, `match subject { arms }`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Match {
+  pub subject: Rc<Node>,
+  pub arms: Vec<Node>,
+}
+
+/// Match arm node, any of `patterns` (literals, ranges or wildcards) matching
+/// the subject and the optional `guard` holding selects `result`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MatchArm {
+  pub patterns: Vec<Node>,
+  pub guard: Option<Rc<Node>>,
+  pub result: Rc<Node>,
+}
+
+/// Range pattern node, e.g. `1..10` or `1..=10`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Range {
+  pub start: Rc<Node>,
+  pub end: Rc<Node>,
+  pub inclusive: bool,
+}
+
 /// Uniary operation node
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct UnaryOp {
@@ -50,10 +234,21 @@ pub struct BinaryOp {
   pub right: Rc<Node>,
 }
 
-/// Attribute node
+/// Range check node, `value between low and high` or, when negated,
+/// `value not between low and high`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Between {
+  pub value: Rc<Node>,
+  pub low: Rc<Node>,
+  pub high: Rc<Node>,
+  pub negated: bool,
+}
+
+/// Attribute node, optionally typed as in `x: address = 0x...`
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Attribute {
   pub ident: Rc<Node>,
+  pub type_expr: Option<Rc<Node>>,
   pub expr: Rc<Node>,
 }
 
@@ -73,6 +268,15 @@ pub enum Operator {
   Divide,
   Modulus,
   Exponent,
+  IntDivide,
+
+  // Bitwise
+  BitAnd,
+  BitOr,
+  BitXor,
+  BitNot,
+  ShiftLeft,
+  ShiftRight,
 
   // Logical
   And,
@@ -101,6 +305,9 @@ pub enum Operator {
 
   // Pipe
   Pipe,
+
+  // Fallback
+  Else,
 }
 
 impl fmt::Display for Operator {
@@ -114,6 +321,15 @@ impl fmt::Display for Operator {
       Divide => "/",
       Modulus => "%",
       Exponent => "^",
+      IntDivide => "//",
+
+      // Bitwise
+      BitAnd => "&",
+      BitOr => "|",
+      BitXor => "xor",
+      BitNot => "~",
+      ShiftLeft => "<<",
+      ShiftRight => ">>",
 
       // Logical
       And => "&&",
@@ -140,6 +356,9 @@ impl fmt::Display for Operator {
       Concatenate => "++",
       Subtract => "--",
       Pipe => "|>",
+
+      // Fallback
+      Else => "else",
     };
     write!(f, "{}", symbol)
   }
@@ -157,6 +376,15 @@ impl TryFrom<&str> for Operator {
       "/" => Ok(Operator::Divide),
       "%" => Ok(Operator::Modulus),
       "^" => Ok(Operator::Exponent),
+      "//" => Ok(Operator::IntDivide),
+
+      // Bitwise
+      "&" => Ok(Operator::BitAnd),
+      "|" => Ok(Operator::BitOr),
+      "xor" => Ok(Operator::BitXor),
+      "~" => Ok(Operator::BitNot),
+      "<<" => Ok(Operator::ShiftLeft),
+      ">>" => Ok(Operator::ShiftRight),
 
       // Logical
       "&&" => Ok(Operator::And),
@@ -184,6 +412,9 @@ impl TryFrom<&str> for Operator {
       "--" => Ok(Operator::Subtract),
       "|>" => Ok(Operator::Pipe),
 
+      // Fallback
+      "else" => Ok(Operator::Else),
+
       _ => Err(TokenError::OperatorError(value.into())),
     }
   }
@@ -202,11 +433,16 @@ pub enum Token {
   Variable(String),
   Option(Opt),
   Address(String),
+  Handle(Handle),
 
   // Basic literal types
   Boolean(bool),
   Number(N),
   Percentage(N),
+  Amount(Amount),
+  /// Durations are normalised to milliseconds
+  Duration(N),
+  Timestamp(String),
   String(String),
   None,
 
@@ -216,9 +452,11 @@ pub enum Token {
   Operator(Operator),
   BinaryOp(BinaryOp),
   UnaryOp(UnaryOp),
+  Between(Between),
 
   // Containers
   List(Vec<Node>),
+  Pair(Pair),
 
   // Body elements
   LineComment(String),
@@ -227,6 +465,25 @@ pub enum Token {
 
   // Body
   Body(Vec<Node>),
+
+  // Statements
+  When(When),
+  Every(Every),
+  Atomic(Atomic),
+  Assert(Assertion),
+  Require(Assertion),
+  Try(Try),
+  For(For),
+  Def(Definition),
+  Return(Option<Rc<Node>>),
+  Import(Import),
+  Annotation(Annotation),
+  Param(Param),
+  Type(TypeExpr),
+  Match(Match),
+  MatchArm(MatchArm),
+  Range(Range),
+  Wildcard,
 }
 
 macro_rules! gen_as {
@@ -274,7 +531,11 @@ impl Token {
   gen_as!(string, Token::String(s), &str, s);
   gen_as!(number, Token::Number(n), &N, n);
   gen_as!(percentage, Token::Percentage(p), &N, p);
+  gen_as!(amount, Token::Amount(a), &Amount, a);
+  gen_as!(duration, Token::Duration(d), &N, d);
+  gen_as!(timestamp, Token::Timestamp(t), &str, t);
   gen_as!(address, Token::Address(a), &str, a);
+  gen_as!(handle, Token::Handle(h), &Handle, h);
   gen_as!(none, Token::None);
 
   gen_as!(function, Token::Function(f), &Function, f);
@@ -282,14 +543,33 @@ impl Token {
   gen_as!(operator, Token::Operator(o), &Operator, o);
   gen_as!(binary_op, Token::BinaryOp(o), &BinaryOp, o);
   gen_as!(unary_op, Token::UnaryOp(u), &UnaryOp, u);
+  gen_as!(between, Token::Between(b), &Between, b);
 
   gen_as!(list, Token::List(l), &Vec<Node>, l);
+  gen_as!(pair, Token::Pair(p), &Pair, p);
 
   gen_as!(line_comment, Token::LineComment(s), &str, s);
   gen_as!(block_comment, Token::BlockComment(s), &str, s);
   gen_as!(attribute, Token::Attribute(a), &Attribute, a);
   gen_as!(option, Token::Option(o), &Opt, o);
   gen_as!(body, Token::Body(b), &Vec<Node>, b);
+  gen_as!(when, Token::When(w), &When, w);
+  gen_as!(every, Token::Every(e), &Every, e);
+  gen_as!(atomic, Token::Atomic(a), &Atomic, a);
+  gen_as!(assert, Token::Assert(a), &Assertion, a);
+  gen_as!(require, Token::Require(a), &Assertion, a);
+  gen_as!(try, Token::Try(t), &Try, t);
+  gen_as!(for, Token::For(f), &For, f);
+  gen_as!(def, Token::Def(d), &Definition, d);
+  gen_as!(return, Token::Return(r), &Option<Rc<Node>>, r);
+  gen_as!(import, Token::Import(i), &Import, i);
+  gen_as!(annotation, Token::Annotation(a), &Annotation, a);
+  gen_as!(param, Token::Param(p), &Param, p);
+  gen_as!(type_expr, Token::Type(t), &TypeExpr, t);
+  gen_as!(match, Token::Match(m), &Match, m);
+  gen_as!(match_arm, Token::MatchArm(a), &MatchArm, a);
+  gen_as!(range, Token::Range(r), &Range, r);
+  gen_as!(wildcard, Token::Wildcard);
 
   pub fn as_boolean(&self) -> Option<bool> {
     match self {
@@ -381,6 +661,39 @@ macro_rules! percentage {
   };
 }
 
+#[macro_export]
+macro_rules! amount {
+  ($s:expr, $u:expr) => {
+    Token::Amount(Amount {
+      value: $s.into(),
+      unit: String::from($u),
+      base_value: None,
+    })
+  };
+
+  ($s:expr, $u:expr, $b:expr) => {
+    Token::Amount(Amount {
+      value: $s.into(),
+      unit: String::from($u),
+      base_value: Some($b.into()),
+    })
+  };
+}
+
+#[macro_export]
+macro_rules! duration {
+  ($s:expr) => {
+    Token::Duration(N::Int($s))
+  };
+}
+
+#[macro_export]
+macro_rules! timestamp {
+  ($s:expr) => {
+    Token::Timestamp(String::from($s))
+  };
+}
+
 #[macro_export]
 macro_rules! boolean {
   (true) => {
@@ -398,6 +711,16 @@ macro_rules! address {
   };
 }
 
+#[macro_export]
+macro_rules! handle {
+  ($n:expr $(, $p:expr)*) => {
+    Token::Handle(Handle {
+      name: String::from($n),
+      path: vec![$(String::from($p),)*],
+    })
+  };
+}
+
 #[macro_export]
 macro_rules! line_comment {
   ($s:expr) => {
@@ -446,6 +769,15 @@ macro_rules! attr {
     ($i:expr, $e:expr) => {
         Token::Attribute(Attribute {
             ident: node!(rc ident!($i)),
+            type_expr: node!(none),
+            expr: node!(rc $e),
+        })
+    };
+
+    ($i:expr, $t:expr, $e:expr) => {
+        Token::Attribute(Attribute {
+            ident: node!(rc ident!($i)),
+            type_expr: some!(node!(rc $t)),
             expr: node!(rc $e),
         })
     };
@@ -468,6 +800,288 @@ macro_rules! list {
     };
 }
 
+#[macro_export]
+macro_rules! pair {
+    ($b:expr, $q:expr) => {
+        Token::Pair(Pair {
+            base: node!(rc $b),
+            quote: node!(rc $q),
+        })
+    };
+}
+
+#[macro_export]
+macro_rules! body {
+    ($($i:expr),*) => {
+        Token::Body(vec![$(node!($i),)*])
+    };
+}
+
+#[macro_export]
+macro_rules! when {
+    ($c:expr, $b:expr) => {
+        Token::When(When {
+            condition: node!(rc $c),
+            body: node!(rc $b),
+        })
+    };
+}
+
+#[macro_export]
+macro_rules! every {
+    ($i:expr, $b:expr) => {
+        Token::Every(Every {
+            interval: node!(rc $i),
+            body: node!(rc $b),
+        })
+    };
+}
+
+#[macro_export]
+macro_rules! atomic {
+    ([$($o:expr),*], $b:expr) => {
+        Token::Atomic(Atomic {
+            options: vec![$(node!($o),)*],
+            body: node!(rc $b),
+        })
+    };
+}
+
+#[macro_export]
+macro_rules! assertion {
+    (assert, $($a:tt)*) => {
+        Token::Assert(assertion!(@args $($a)*))
+    };
+
+    (require, $($a:tt)*) => {
+        Token::Require(assertion!(@args $($a)*))
+    };
+
+    (@args $c:expr) => {
+        Assertion {
+            condition: node!(rc $c),
+            message: node!(none),
+        }
+    };
+
+    (@args $c:expr, $m:expr) => {
+        Assertion {
+            condition: node!(rc $c),
+            message: some!(node!(rc $m)),
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! try_catch {
+    ($b:expr, none, $c:expr) => {
+        Token::Try(Try {
+            body: node!(rc $b),
+            error: node!(none),
+            catch: node!(rc $c),
+        })
+    };
+
+    ($b:expr, $e:expr, $c:expr) => {
+        Token::Try(Try {
+            body: node!(rc $b),
+            error: some!(node!(rc ident!($e))),
+            catch: node!(rc $c),
+        })
+    };
+}
+
+#[macro_export]
+macro_rules! for_loop {
+    ($i:expr, $e:expr, $b:expr) => {
+        Token::For(For {
+            binding: node!(rc ident!($i)),
+            iterable: node!(rc $e),
+            body: node!(rc $b),
+        })
+    };
+}
+
+#[macro_export]
+macro_rules! def {
+    ($n:expr, [$($p:expr),*], $b:expr) => {
+        Token::Def(Definition {
+            name: node!(rc ident!($n)),
+            params: vec![$(node!($p),)*],
+            body: node!(rc $b),
+        })
+    };
+}
+
+#[macro_export]
+macro_rules! ret {
+    () => {
+        Token::Return(node!(none))
+    };
+
+    ($e:expr) => {
+        Token::Return(some!(node!(rc $e)))
+    };
+}
+
+#[macro_export]
+macro_rules! import {
+    ($p:expr) => {
+        Token::Import(Import {
+            path: String::from($p),
+            alias: node!(none),
+            resolved: None,
+        })
+    };
+
+    ($p:expr => $r:expr) => {
+        Token::Import(Import {
+            path: String::from($p),
+            alias: node!(none),
+            resolved: Some(String::from($r)),
+        })
+    };
+
+    ($p:expr, $a:expr) => {
+        Token::Import(Import {
+            path: String::from($p),
+            alias: some!(node!(rc ident!($a))),
+            resolved: None,
+        })
+    };
+
+    ($p:expr, $a:expr => $r:expr) => {
+        Token::Import(Import {
+            path: String::from($p),
+            alias: some!(node!(rc ident!($a))),
+            resolved: Some(String::from($r)),
+        })
+    };
+}
+
+#[macro_export]
+macro_rules! annotation {
+    ($n:expr $(, $a:expr)*) => {
+        Token::Annotation(Annotation {
+            name: node!(rc ident!($n)),
+            args: vec![$(node!($a),)*],
+        })
+    };
+}
+
+#[macro_export]
+macro_rules! param {
+    ($n:expr, none, none) => {
+        Token::Param(Param {
+            name: node!(rc ident!($n)),
+            type_expr: node!(none),
+            default: node!(none),
+        })
+    };
+
+    ($n:expr, none, $d:expr) => {
+        Token::Param(Param {
+            name: node!(rc ident!($n)),
+            type_expr: node!(none),
+            default: some!(node!(rc $d)),
+        })
+    };
+
+    ($n:expr, $t:expr, none) => {
+        Token::Param(Param {
+            name: node!(rc ident!($n)),
+            type_expr: some!(node!(rc $t)),
+            default: node!(none),
+        })
+    };
+
+    ($n:expr, $t:expr, $d:expr) => {
+        Token::Param(Param {
+            name: node!(rc ident!($n)),
+            type_expr: some!(node!(rc $t)),
+            default: some!(node!(rc $d)),
+        })
+    };
+}
+
+#[macro_export]
+macro_rules! type_expr {
+    ($n:expr) => {
+        type_expr!($n, [])
+    };
+
+    ($n:expr, [$($a:expr),*]) => {
+        Token::Type(TypeExpr {
+            name: String::from($n),
+            args: vec![$(node!($a),)*],
+            optional: false,
+        })
+    };
+
+    ($n:expr, [$($a:expr),*], optional) => {
+        Token::Type(TypeExpr {
+            name: String::from($n),
+            args: vec![$(node!($a),)*],
+            optional: true,
+        })
+    };
+}
+
+#[macro_export]
+macro_rules! match_expr {
+    ($s:expr $(, $a:expr)*) => {
+        Token::Match(Match {
+            subject: node!(rc $s),
+            arms: vec![$(node!($a),)*],
+        })
+    };
+}
+
+#[macro_export]
+macro_rules! match_arm {
+    ([$($p:expr),*], none, $r:expr) => {
+        Token::MatchArm(MatchArm {
+            patterns: vec![$(node!($p),)*],
+            guard: node!(none),
+            result: node!(rc $r),
+        })
+    };
+
+    ([$($p:expr),*], $g:expr, $r:expr) => {
+        Token::MatchArm(MatchArm {
+            patterns: vec![$(node!($p),)*],
+            guard: some!(node!(rc $g)),
+            result: node!(rc $r),
+        })
+    };
+}
+
+#[macro_export]
+macro_rules! range {
+    ($s:expr, $e:expr) => {
+        Token::Range(Range {
+            start: node!(rc $s),
+            end: node!(rc $e),
+            inclusive: false,
+        })
+    };
+
+    ($s:expr, $e:expr, inclusive) => {
+        Token::Range(Range {
+            start: node!(rc $s),
+            end: node!(rc $e),
+            inclusive: true,
+        })
+    };
+}
+
+#[macro_export]
+macro_rules! wildcard {
+  () => {
+    Token::Wildcard
+  };
+}
+
 #[macro_export]
 macro_rules! unary_op {
     ($o:expr, $i:expr) => {
@@ -489,6 +1103,27 @@ macro_rules! binary_op {
     };
 }
 
+#[macro_export]
+macro_rules! between {
+    ($v:expr, $l:expr, $h:expr) => {
+        Token::Between(Between {
+            value: node!(rc $v),
+            low: node!(rc $l),
+            high: node!(rc $h),
+            negated: false,
+        })
+    };
+
+    ($v:expr, $l:expr, $h:expr, negated) => {
+        Token::Between(Between {
+            value: node!(rc $v),
+            low: node!(rc $l),
+            high: node!(rc $h),
+            negated: true,
+        })
+    };
+}
+
 #[macro_export]
 macro_rules! conditional {
     ($t:expr, $l:expr) => {
@@ -535,4 +1170,44 @@ mod test {
     assert_eq!(f.as_boolean(), Some(false));
     assert_eq!(f.as_false(), Some(()));
   }
+
+  #[test]
+  fn operator_round_trip() {
+    use Operator::*;
+    for op in [
+      Plus,
+      Minus,
+      Multiply,
+      Divide,
+      Modulus,
+      Exponent,
+      IntDivide,
+      BitAnd,
+      BitOr,
+      BitXor,
+      BitNot,
+      ShiftLeft,
+      ShiftRight,
+      And,
+      Or,
+      Not,
+      Equal,
+      NotEqual,
+      Greater,
+      Less,
+      GreaterEqual,
+      LessEqual,
+      In,
+      AttrAccess,
+      IndexAccess,
+      Concatenate,
+      Subtract,
+      Pipe,
+      Else,
+    ]
+    .iter()
+    {
+      assert_eq!(Operator::try_from(op.to_string().as_str()).unwrap(), *op);
+    }
+  }
 }
@@ -0,0 +1,104 @@
+use std::rc::Rc;
+
+use nom::{
+  bytes::complete::tag_no_case,
+  character::complete::{multispace0, space1},
+  combinator::map,
+  sequence::{terminated, tuple},
+};
+use nom_tracable::tracable_parser;
+
+use crate::{body, expression, Every, Node, Result, Span, Token, When};
+
+/// `when <condition> { ... }`, the body runs whenever the condition holds
+#[tracable_parser]
+pub fn when_block(i: Span) -> Result {
+  map(
+    tuple((terminated(tag_no_case("when"), space1), expression, multispace0, body)),
+    |(keyword, condition, _, body)| {
+      let when = When {
+        condition: Rc::new(condition),
+        body: Rc::new(body),
+      };
+      Node::new(Token::When(when), &keyword)
+    },
+  )(i)
+}
+
+/// `every <interval> { ... }`, the body runs on a schedule, e.g. `every 5m { ... }`
+#[tracable_parser]
+pub fn every_block(i: Span) -> Result {
+  map(
+    tuple((terminated(tag_no_case("every"), space1), expression, multispace0, body)),
+    |(keyword, interval, _, body)| {
+      let every = Every {
+        interval: Rc::new(interval),
+        body: Rc::new(body),
+      };
+      Node::new(Token::Every(every), &keyword)
+    },
+  )(i)
+}
+
+#[cfg(test)]
+mod test {
+  use crate::test::{info, Result};
+  use crate::*;
+  use nom::{branch::alt, combinator::all_consuming};
+  use nom_tracable::TracableInfo;
+  use rstest::rstest;
+  use std::convert::TryFrom;
+
+  #[rstest(input, expected,
+      case(
+        "when price(eth) > 2000 { sell(eth) }",
+        when!(
+          binary_op!(function!("price", none, ident!("eth")), ">", number!(2000)),
+          body!(function!("sell", none, ident!("eth")))
+        )
+      ),
+      case("WHEN ready {}", when!(ident!("ready"), body!())),
+      case(
+        "when ready\n{\n  # comment\n  a(); b()\n\n  c() # trailing\n}",
+        when!(ident!("ready"), body!(function!("a"), function!("b"), function!("c")))
+      ),
+      case(
+        "when ready { every 1h { a() } }",
+        when!(ident!("ready"), body!(every!(duration!(3_600_000), body!(function!("a")))))
+      ),
+    )]
+  fn test_when_block(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let (span, node) = when_block(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    node.assert_same_token(&node!(expected));
+    Ok(())
+  }
+
+  #[rstest(input, expected,
+      case("every 5m { rebalance() }", every!(duration!(300_000), body!(function!("rebalance")))),
+      case(
+        "Every 1d12h {\n  a()\n  b()\n}",
+        every!(duration!(129_600_000), body!(function!("a"), function!("b")))
+      ),
+      case("every interval { a() }", every!(ident!("interval"), body!(function!("a")))),
+    )]
+  fn test_every_block(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let (span, node) = every_block(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    node.assert_same_token(&node!(expected));
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("when ready"),
+    case("when { a() }"),
+    case("whenready { a() }"),
+    case("when ready { a() b() }"),
+    case("every 5m { a()")
+  )]
+  fn test_trigger_block_invalid(input: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(all_consuming(alt((when_block, every_block)))(span).is_err());
+  }
+}
@@ -0,0 +1,81 @@
+use nom::{
+  bytes::complete::take_while1,
+  character::complete::{char, multispace0, space0},
+  combinator::{map, opt},
+  multi::separated_list1,
+  sequence::{delimited, pair, preceded, tuple},
+};
+use nom_tracable::tracable_parser;
+
+use crate::{valid_ident_char_a, Node, Result, Span, Token, TypeExpr};
+
+/// Type expressions such as `address`, `list<address>`, `map<string, number>`
+/// or `address?` for an optional value. Names are lowercased like identifiers.
+#[tracable_parser]
+pub fn type_expr(i: Span) -> Result {
+  map(
+    tuple((
+      take_while1(valid_ident_char_a),
+      opt(delimited(
+        pair(char('<'), multispace0),
+        separated_list1(delimited(multispace0, char(','), multispace0), type_expr),
+        pair(multispace0, char('>')),
+      )),
+      opt(char('?')),
+    )),
+    |(name, args, optional): (Span, _, _)| {
+      let t = TypeExpr {
+        name: name.fragment().to_lowercase(),
+        args: args.unwrap_or_default(),
+        optional: optional.is_some(),
+      };
+      Node::new(Token::Type(t), &name)
+    },
+  )(i)
+}
+
+/// A `: type` annotation following a name
+#[tracable_parser]
+pub(crate) fn type_annotation(i: Span) -> Result {
+  preceded(tuple((space0, char(':'), space0)), type_expr)(i)
+}
+
+#[cfg(test)]
+mod test {
+  use crate::test::{info, Result};
+  use crate::*;
+  use nom::combinator::all_consuming;
+  use nom_tracable::TracableInfo;
+  use rstest::rstest;
+
+  #[rstest(input, expected,
+      case("address", type_expr!("address")),
+      case("Number", type_expr!("number")),
+      case("address?", type_expr!("address", [], optional)),
+      case("list<address>", type_expr!("list", [type_expr!("address")])),
+      case("list< address? >?", type_expr!("list", [type_expr!("address", [], optional)], optional)),
+      case(
+        "map<string, list<number>>",
+        type_expr!("map", [type_expr!("string"), type_expr!("list", [type_expr!("number")])])
+      ),
+    )]
+  fn test_type_expr(input: &'static str, expected: Token, info: TracableInfo) -> Result {
+    let (span, node) = type_expr(Span::new_extra(input, info))?;
+    assert!(span.fragment().is_empty());
+    node.assert_same_token(&node!(expected));
+    Ok(())
+  }
+
+  #[rstest(
+    input,
+    case("list<>"),
+    case("list<address"),
+    case("list<address,>"),
+    case("address??"),
+    case("<address>")
+  )]
+  fn test_type_expr_invalid(input: &'static str, info: TracableInfo) {
+    let span = Span::new_extra(input, info);
+    assert!(all_consuming(type_expr)(span).is_err());
+  }
+}
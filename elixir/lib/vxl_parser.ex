@@ -18,6 +18,21 @@ defmodule VXLParser do
   @spec parse(String.t()) :: {:ok, String.t()} | {:error, :parse_error}
   def parse(_input), do: error()
 
+  @doc """
+  Parses the file at `path` and everything it imports to one VXL AST (json),
+  with `sources` mapping each path to the file's contents
+  """
+  @spec parse_files(String.t(), %{String.t() => String.t()}) :: {:ok, String.t()} | {:error, :parse_error}
+  def parse_files(_path, _sources), do: error()
+
+  @doc """
+  Parses input to VXL AST (json), with `denominations` mapping additional units
+  to their number of decimals, which amounts are scaled into base units by
+  """
+  @spec parse_with_denominations(String.t(), %{String.t() => non_neg_integer()}) ::
+          {:ok, String.t()} | {:error, :parse_error}
+  def parse_with_denominations(_input, _denominations), do: error()
+
   @spec build_info() :: BuildInfo.t()
   def build_info, do: error()
 
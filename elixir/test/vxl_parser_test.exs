@@ -23,6 +23,32 @@ defmodule VXLParserTest do
               "[{\"offset\":0,\"line\":1,\"column\":1,\"token\":{\"number\":{\"decimal\":\"0.3333333333333333333333333333\"}}}]"}
   end
 
+  test "successfully parses files" do
+    assert {:ok, json} =
+             VXLParser.parse_files("main.vxl", %{
+               "main.vxl" => "import \"lib.vxl\"\nmain()",
+               "lib.vxl" => "lib()"
+             })
+
+    assert json =~ "\"source\":\"lib.vxl\""
+    assert json =~ "\"source\":\"main.vxl\""
+    assert json =~ "\"resolved\":\"lib.vxl\""
+    assert json =~ "\"files\":{\"lib.vxl\":{\"version\":2},\"main.vxl\":{\"version\":2}}"
+  end
+
+  test "fails to parse a missing import" do
+    assert VXLParser.parse_files("main.vxl", %{"main.vxl" => "import \"lib.vxl\""}) == {:error, :parse_error}
+  end
+
+  test "successfully parses with denominations" do
+    assert {:ok, json} = VXLParser.parse_with_denominations("swap(250.5 USDC)", %{"usdc" => 6})
+    assert json =~ "\"base_value\":{\"int\":\"250500000\"}"
+  end
+
+  test "fails to parse amounts finer than their denomination" do
+    assert VXLParser.parse_with_denominations("swap(0.0000001 USDC)", %{"usdc" => 6}) == {:error, :parse_error}
+  end
+
   test "fails to parses function" do
     assert VXLParser.parse(";-123.") == {:error, :parse_error}
   end
@@ -1,4 +1,12 @@
 mod build_info;
 mod parser;
 
-rustler::init!("Elixir.VXLParser", [parser::parse, build_info::build_info]);
+rustler::init!(
+  "Elixir.VXLParser",
+  [
+    parser::parse,
+    parser::parse_files,
+    parser::parse_with_denominations,
+    build_info::build_info
+  ]
+);
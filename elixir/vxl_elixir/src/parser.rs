@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use rustler::{Atom, Error, NifResult as Result};
 use serde_json::to_string;
 
@@ -19,3 +21,25 @@ fn parse(input: &str) -> Result<(Atom, String)> {
   let json = to_string(&result).map_err(|_| Error::Term(Box::new(atoms::json_error())))?;
   Ok((atoms::ok(), json))
 }
+
+/// Parses the file at `path` and everything it imports into one program, with
+/// `sources` mapping each path to the file's contents
+#[rustler::nif(schedule = "DirtyCpu")]
+fn parse_files(path: &str, sources: HashMap<String, String>) -> Result<(Atom, String)> {
+  let result = core::parse_file(path, &sources).map_err(|_| Error::Term(Box::new(atoms::parse_error())))?;
+  let json = to_string(&result).map_err(|_| Error::Term(Box::new(atoms::json_error())))?;
+  Ok((atoms::ok(), json))
+}
+
+/// Parses with `denominations` mapping additional units (e.g. `usdc`) to their
+/// number of decimals, which amounts are scaled into base units by
+#[rustler::nif(schedule = "DirtyCpu")]
+fn parse_with_denominations(input: &str, denominations: HashMap<String, u32>) -> Result<(Atom, String)> {
+  let mut units = core::Denominations::default();
+  for (unit, decimals) in &denominations {
+    units.register(unit, *decimals);
+  }
+  let result = core::parse_with_denominations(input, units).map_err(|_| Error::Term(Box::new(atoms::parse_error())))?;
+  let json = to_string(&result).map_err(|_| Error::Term(Box::new(atoms::json_error())))?;
+  Ok((atoms::ok(), json))
+}
@@ -1,5 +1,5 @@
 pub use self::build_info::build_info;
-pub use self::parser::parse;
+pub use self::parser::{parse, parse_files, parse_with_denominations};
 
 use error::set_panic_hook;
 use wasm_bindgen::prelude::wasm_bindgen;
@@ -1,4 +1,6 @@
-use serde_wasm_bindgen::to_value;
+use std::collections::HashMap;
+
+use serde_wasm_bindgen::{from_value, to_value};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -7,3 +9,25 @@ pub fn parse(input: &str) -> Result<JsValue, JsValue> {
   let result = core::parse(input).map_err(|err| JsValue::from(err.to_string()))?;
   to_value(&result).map_err(|err| err.into())
 }
+
+/// Parses the file at `path` and everything it imports into one program, with
+/// `sources` mapping each path to the file's contents
+#[wasm_bindgen]
+pub fn parse_files(path: &str, sources: JsValue) -> Result<JsValue, JsValue> {
+  let sources: HashMap<String, String> = from_value(sources)?;
+  let result = core::parse_file(path, &sources).map_err(|err| JsValue::from(err.to_string()))?;
+  to_value(&result).map_err(|err| err.into())
+}
+
+/// Parses with `denominations` mapping additional units (e.g. `usdc`) to their
+/// number of decimals, which amounts are scaled into base units by
+#[wasm_bindgen(js_name = parseWithDenominations)]
+pub fn parse_with_denominations(input: &str, denominations: JsValue) -> Result<JsValue, JsValue> {
+  let units: HashMap<String, u32> = from_value(denominations)?;
+  let mut denominations = core::Denominations::default();
+  for (unit, decimals) in &units {
+    denominations.register(unit, *decimals);
+  }
+  let result = core::parse_with_denominations(input, denominations).map_err(|err| JsValue::from(err.to_string()))?;
+  to_value(&result).map_err(|err| err.into())
+}
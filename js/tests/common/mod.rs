@@ -1,8 +1,28 @@
+use std::collections::HashMap;
+
+use serde_wasm_bindgen::to_value;
 use wasm_bindgen_test::*;
 
-use vxl_wasm::parse;
+use vxl_wasm::{parse, parse_files, parse_with_denominations};
 
 #[wasm_bindgen_test]
 pub fn parses() {
   let _ = parse("function.subfunction(10, false, \"hello\")");
 }
+
+#[wasm_bindgen_test]
+pub fn parses_files() {
+  let sources: HashMap<&str, &str> = [("main.vxl", "import \"lib.vxl\"\nmain()"), ("lib.vxl", "lib()")]
+    .iter()
+    .cloned()
+    .collect();
+  assert!(parse_files("main.vxl", to_value(&sources).unwrap()).is_ok());
+  assert!(parse_files("missing.vxl", to_value(&sources).unwrap()).is_err());
+}
+
+#[wasm_bindgen_test]
+pub fn parses_with_denominations() {
+  let denominations: HashMap<&str, u32> = [("usdc", 6)].iter().cloned().collect();
+  assert!(parse_with_denominations("swap(250.5 USDC)", to_value(&denominations).unwrap()).is_ok());
+  assert!(parse_with_denominations("swap(0.0000001 USDC)", to_value(&denominations).unwrap()).is_err());
+}